@@ -18,12 +18,14 @@ def networkx_cliques(A):
 # HERON
 def heron_cliques(A):
     adjacency_matrix = [np.nonzero(row)[0].tolist() for row in A]
-    return he.find_cliques(adjacency_matrix)
+    result = he.find_cliques(adjacency_matrix)
+    return result.elapsed_ns / 1e9, result.count
 
 
 def heron_gradual_cliques(A):
     adjacency_matrix = [np.nonzero(row)[0].tolist() for row in A]
-    return he.cliques_up_to(adjacency_matrix)
+    result = he.cliques_up_to(adjacency_matrix)
+    return [ns / 1e9 for ns in result.timings_ns], result.counts
 
 
 def main():
@@ -46,9 +48,11 @@ def main():
     print("HERON")
     print(f"elapsed: {elapsed}, count: {count}")
 
-    elapsed, count = heron_gradual_cliques(A)
+    timings, counts = heron_gradual_cliques(A)
     print("HERON (gradual)")
-    print(f"elapsed: {elapsed}, count: {count}")
+    print(f"elapsed: {sum(timings)}, count: {sum(counts)}")
+    for size, (elapsed, count) in enumerate(zip(timings, counts), start=1):
+        print(f"  size {size}: elapsed: {elapsed}, count: {count}")
 
 
 if __name__ == "__main__":
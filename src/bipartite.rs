@@ -0,0 +1,241 @@
+use crate::bitvec::Bitvec;
+use std::ops::ControlFlow;
+
+/// Bipartite graph stored as bitsets: a row per left vertex over the right vertices.
+#[derive(Clone)]
+pub struct BipartiteMatrix {
+    pub rows: Vec<Bitvec>,
+    n_right: usize,
+}
+
+impl BipartiteMatrix {
+    /// Builds the graph from the right-hand neighbours of each left vertex.
+    pub fn new(neighbours: Vec<Vec<usize>>, n_right: usize) -> Self {
+        let rows = neighbours
+            .iter()
+            .map(|row| Bitvec::from_vector(row, n_right))
+            .collect();
+        BipartiteMatrix { rows, n_right }
+    }
+    /// Builds the graph from a biadjacency matrix: entry `(u, v)` is non-zero when left
+    /// vertex `u` is adjacent to right vertex `v`. All rows must have the same length.
+    pub fn from_biadjacency(biadjacency: &[Vec<usize>]) -> Self {
+        let n_right = biadjacency.first().map_or(0, |row| row.len());
+        let neighbours = biadjacency
+            .iter()
+            .map(|row| {
+                assert_eq!(row.len(), n_right, "biadjacency rows differ in length");
+                (0..n_right).filter(|&v| row[v] != 0).collect()
+            })
+            .collect();
+        BipartiteMatrix::new(neighbours, n_right)
+    }
+    /// Builds the graph from `(left, right)` edges; repeated edges are merged.
+    pub fn from_edges(edges: &[(usize, usize)], n_left: usize, n_right: usize) -> Self {
+        let mut neighbours = vec![Vec::new(); n_left];
+        for &(u, v) in edges {
+            assert!(
+                u < n_left && v < n_right,
+                "edge ({}, {}) out of range",
+                u,
+                v
+            );
+            neighbours[u].push(v);
+        }
+        BipartiteMatrix::new(neighbours, n_right)
+    }
+    pub fn n_left(&self) -> usize {
+        self.rows.len()
+    }
+    pub fn n_right(&self) -> usize {
+        self.n_right
+    }
+}
+
+/// Receives maximal bicliques, left side then right side, each sorted.
+pub trait BicliqueVisitor {
+    fn visit(&mut self, left: &[usize], right: &[usize]) -> ControlFlow<()>;
+}
+
+impl<F: FnMut(&[usize], &[usize]) -> ControlFlow<()>> BicliqueVisitor for F {
+    fn visit(&mut self, left: &[usize], right: &[usize]) -> ControlFlow<()> {
+        self(left, right)
+    }
+}
+
+/// MBEA-style search (Zhang et al., 2014): the left side grows one candidate at a time,
+/// the right side being every vertex adjacent to all of it. Candidates adjacent to the
+/// whole right side join at once, and a branch is dropped when an excluded vertex is, as
+/// it would only repeat bicliques found earlier. As in iMBEA, candidates are taken in
+/// increasing order of common neighbourhood, and those with the same right neighbours as
+/// the one taken are settled with it.
+struct BicliqueSearch<'a, V> {
+    graph: &'a BipartiteMatrix,
+    min_left: usize,
+    min_right: usize,
+    visitor: &'a mut V,
+}
+
+impl<V: BicliqueVisitor> BicliqueSearch<'_, V> {
+    /// Keeps the vertices of `pool` with at least `min_right` neighbours in `right`, with
+    /// their neighbour count; only those can extend a large enough biclique.
+    fn shared(&self, pool: &[usize], right: &Bitvec) -> Vec<(usize, usize)> {
+        pool.iter()
+            .map(|&u| (u, right.intersection_count(&self.graph.rows[u])))
+            .filter(|&(_, shared)| shared >= self.min_right)
+            .collect()
+    }
+
+    fn expand(
+        &mut self,
+        right: &Bitvec,
+        left: &[usize],
+        mut cand: Vec<(usize, usize)>,
+        mut excl: Vec<usize>,
+    ) -> ControlFlow<()> {
+        cand.sort_unstable_by_key(|&(u, shared)| (shared, u));
+        while let Some(&(x, _)) = cand.first() {
+            if left.len() + cand.len() < self.min_left {
+                break;
+            }
+            let next_right = right.intersection(&self.graph.rows[x]);
+            let n_next_right = next_right.n_elements();
+            let mut absorbed = vec![x];
+            let next_excl = self.shared(&excl, &next_right);
+            if next_excl.iter().all(|&(_, shared)| shared < n_next_right) {
+                let mut next_left = left.to_vec();
+                next_left.push(x);
+                let mut next_cand = Vec::new();
+                for &(u, in_right) in &cand[1..] {
+                    let shared = next_right.intersection_count(&self.graph.rows[u]);
+                    if shared < self.min_right {
+                        continue;
+                    }
+                    if shared == n_next_right {
+                        next_left.push(u);
+                        // same right neighbours as x: every branch of u is this one's
+                        if in_right == n_next_right {
+                            absorbed.push(u);
+                        }
+                    } else {
+                        next_cand.push((u, shared));
+                    }
+                }
+                if next_left.len() >= self.min_left {
+                    let mut sorted = next_left.clone();
+                    sorted.sort_unstable();
+                    self.visitor.visit(&sorted, &next_right.elements())?;
+                }
+                if !next_cand.is_empty() && next_left.len() + next_cand.len() >= self.min_left {
+                    let next_excl = next_excl.into_iter().map(|(u, _)| u).collect();
+                    self.expand(&next_right, &next_left, next_cand, next_excl)?;
+                }
+            }
+            cand.retain(|(u, _)| !absorbed.contains(u));
+            excl.extend(absorbed);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Maximal bicliques: pairs of non-empty vertex sets, one on each side, with every left
+/// vertex adjacent to every right vertex and neither side extendable.
+pub trait Bicliques {
+    /// Hands every maximal biclique with at least `min_left` left and `min_right` right
+    /// vertices to `visitor` until it asks to stop. Returns `ControlFlow::Break` if the
+    /// visitor stopped the enumeration early.
+    fn visit_bicliques<V: BicliqueVisitor>(
+        &self,
+        min_left: usize,
+        min_right: usize,
+        visitor: &mut V,
+    ) -> ControlFlow<()>;
+}
+
+impl Bicliques for BipartiteMatrix {
+    fn visit_bicliques<V: BicliqueVisitor>(
+        &self,
+        min_left: usize,
+        min_right: usize,
+        visitor: &mut V,
+    ) -> ControlFlow<()> {
+        let mut search = BicliqueSearch {
+            graph: self,
+            min_left: min_left.max(1),
+            min_right: min_right.max(1),
+            visitor,
+        };
+        let n_right = self.n_right();
+        let right = Bitvec::from_vector(&(0..n_right).collect::<Vec<usize>>(), n_right);
+        let cand = search.shared(&(0..self.n_left()).collect::<Vec<usize>>(), &right);
+        search.expand(&right, &[], cand, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bicliques() {
+        let (n_left, n_right) = (9, 8);
+        let edges: Vec<(usize, usize)> = (0..n_left)
+            .flat_map(|u| (0..n_right).map(move |v| (u, v)))
+            .filter(|&(u, v)| (u * 5 + v * 3 + u * v) % 7 < 4)
+            .collect();
+        let graph = BipartiteMatrix::from_edges(&edges, n_left, n_right);
+        let biadjacency: Vec<Vec<usize>> = (0..n_left)
+            .map(|u| {
+                (0..n_right)
+                    .map(|v| graph.rows[u].contains(v) as usize)
+                    .collect()
+            })
+            .collect();
+        let rebuilt = BipartiteMatrix::from_biadjacency(&biadjacency);
+        for u in 0..n_left {
+            assert_eq!(rebuilt.rows[u].elements(), graph.rows[u].elements());
+        }
+
+        // every closed right side: the common neighbours of its common neighbours
+        let mut expected = Vec::new();
+        for mask in 1u32..1 << n_right {
+            let right: Vec<usize> = (0..n_right).filter(|&v| mask >> v & 1 == 1).collect();
+            let left: Vec<usize> = (0..n_left)
+                .filter(|&u| right.iter().all(|&v| graph.rows[u].contains(v)))
+                .collect();
+            let closed = (0..n_right)
+                .filter(|&v| left.iter().all(|&u| graph.rows[u].contains(v)))
+                .count()
+                == right.len();
+            if !left.is_empty() && closed {
+                expected.push((left, right));
+            }
+        }
+        expected.sort();
+        let bicliques = |min_left, min_right| {
+            let mut found = Vec::new();
+            let flow = graph.visit_bicliques(
+                min_left,
+                min_right,
+                &mut |left: &[usize], right: &[usize]| {
+                    found.push((left.to_vec(), right.to_vec()));
+                    ControlFlow::Continue(())
+                },
+            );
+            assert!(flow.is_continue());
+            found.sort();
+            found
+        };
+        assert_eq!(bicliques(1, 1), expected);
+
+        expected.retain(|(left, right)| left.len() >= 2 && right.len() >= 3);
+        assert_eq!(bicliques(2, 3), expected);
+
+        let mut seen = 0;
+        let flow = graph.visit_bicliques(1, 1, &mut |_: &[usize], _: &[usize]| {
+            seen += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!((flow, seen), (ControlFlow::Break(()), 1));
+    }
+}
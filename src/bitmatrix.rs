@@ -1,5 +1,5 @@
 use crate::bitvec::Bitvec;
-use hashbrown::HashMap;
+use crate::levels::{CliqueLevel, LevelChunks};
 
 #[derive(Clone)]
 pub struct Bitmatrix {
@@ -16,15 +16,42 @@ impl Bitmatrix {
         }
         Bitmatrix { matrix, capacity }
     }
+    pub fn n_nodes(&self) -> usize {
+        self.capacity
+    }
     pub fn get_neighbours(&self, u: usize) -> Vec<usize> {
         self.matrix[u].elements()
     }
+    /// Subgraph induced by `vertices`, relabelled so that `vertices[i]` becomes vertex `i`.
+    pub fn induced_subgraph(&self, vertices: &[usize]) -> Bitmatrix {
+        let rows: Vec<Vec<usize>> = vertices
+            .iter()
+            .map(|&w| {
+                (0..vertices.len())
+                    .filter(|&j| self.matrix[w].contains(vertices[j]))
+                    .collect()
+            })
+            .collect();
+        Bitmatrix::new(rows, vertices.len())
+    }
+    /// Complement graph: two distinct vertices are adjacent exactly when they are not
+    /// adjacent here.
+    pub fn complement(&self) -> Bitmatrix {
+        let n_nodes = self.n_nodes();
+        let rows: Vec<Vec<usize>> = (0..n_nodes)
+            .map(|u| {
+                (0..n_nodes)
+                    .filter(|&v| v != u && !self.matrix[u].contains(v))
+                    .collect()
+            })
+            .collect();
+        Bitmatrix::new(rows, n_nodes)
+    }
 }
 
 pub trait AllCliques {
     // Define the methods or associated types here
     fn get_max_degree(&self, pool: &Bitvec) -> Option<usize>;
-    fn all_cliques(&self) -> usize;
 }
 
 impl AllCliques for Bitmatrix {
@@ -34,161 +61,125 @@ impl AllCliques for Bitmatrix {
             .max_by_key(|u| self.matrix[**u].n_elements())
             .cloned()
     }
+}
 
-    fn all_cliques(&self) -> usize {
-        let mut Q: Vec<Option<usize>> = Vec::new();
-        let mut count = 0;
-        Q.push(None);
-        let mut cand = Bitvec::new(self.capacity);
-        for i in 0..self.capacity {
-            cand.insert(i)
-        }
-        let mut subg = cand.clone();
-        let mut stack = Vec::new();
-        let mut u = match self.get_max_degree(&cand) {
-            Some(elem) => elem,
-            None => return count,
-        };
-        let mut ext_u = cand.difference(&self.matrix[u]);
-        while !Q.is_empty() | !stack.is_empty() | !ext_u.is_empty() {
-            match ext_u.pop() {
-                Some(q) => {
-                    cand.remove(q);
-                    match Q.last_mut() {
-                        Some(last) => *last = Some(q),
-                        None => break,
-                    };
-                    let adj_q = &self.matrix[q];
-                    let subg_q = subg.intersection(adj_q);
-                    if subg_q.is_empty() {
-                        // println!("{:?}", Q)
-                        count += 1;
-                    } else {
-                        let cand_q = cand.intersection(adj_q);
-                        if !cand_q.is_empty() {
-                            stack.push((subg, cand, ext_u));
-                            Q.push(None); //???
-                            subg = subg_q;
-                            cand = cand_q;
-                            match self.get_max_degree(&cand.intersection(&self.matrix[u])) {
-                                Some(index) => {
-                                    u = index;
-                                    ext_u = cand.difference(&self.matrix[u]);
-                                }
-                                None => ext_u = cand.difference(&self.matrix[u]),
-                            };
-                        }
-                    }
-                }
-                None => {
-                    Q.pop();
-                    if let Some((subg_prev, cand_prev, ext_u_prev)) = stack.pop() {
-                        subg = subg_prev;
-                        cand = cand_prev;
-                        ext_u = ext_u_prev;
-                    } else {
-                        break; // Break the loop if the stack is empty
-                    }
-                }
-            }
-        }
-        count
+/// Counts one more clique of `size` vertices in a histogram indexed by `size - 1`.
+pub fn add_to_histogram(histogram: &mut Vec<u64>, size: usize) {
+    if histogram.len() < size {
+        histogram.resize(size, 0);
+    }
+    histogram[size - 1] += 1;
+}
+
+/// Adds two histograms built with `add_to_histogram`.
+pub fn merge_histograms(mut a: Vec<u64>, b: Vec<u64>) -> Vec<u64> {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0);
+    }
+    for (total, count) in a.iter_mut().zip(b) {
+        *total += count;
     }
+    a
 }
 
 /// Pretty efficient algoritghm for getting cliques, if I do say so myself.
 /// receives a list of N dimensional cliques and returns all N+1 dimensional cliques.
 /// It just duplicates and avoids lower degree nodes etc cannot think of better optimizations.
 pub trait NextCliques {
-    fn get_next_cliques(&self, cliques: &[Bitvec]) -> Vec<Bitvec>;
+    /// Next level of the clique complex, every clique of `cliques` grown by one vertex,
+    /// streamed in chunks of about `chunk_size` cliques.
+    fn next_cliques_chunked<'a>(
+        &'a self,
+        cliques: &'a CliqueLevel,
+        chunk_size: usize,
+    ) -> LevelChunks<'a>;
 }
 
 impl NextCliques for Bitmatrix {
-    fn get_next_cliques(&self, cliques: &[Bitvec]) -> Vec<Bitvec> {
-        let mut new_cliques: Vec<Bitvec> = Vec::new();
-        let degrees: Vec<usize> = self.matrix.iter().map(|row| row.n_elements()).collect();
-        for clique in cliques.iter() {
-            let clique_size = clique.n_elements();
-            let vertii = clique.elements();
-            //get common neighbours of cliques
-            let mut common_neighbours: Bitvec;
-            match vertii.first() {
-                Some(first) => {
-                    common_neighbours = self.matrix[*first].clone();
-                    for vertex in vertii.iter().skip(1) {
-                        common_neighbours.intersection_with(&self.matrix[*vertex]);
-                    }
-                }
-                None => continue,
-            }
-            match vertii.last() {
-                Some(vertex) => {
-                    for neighbour in common_neighbours.elements_from(*vertex).into_iter() {
-                        if degrees[neighbour] < clique_size {
-                            continue;
-                        }
-                        if self.matrix[neighbour].contains_all(&vertii) {
-                            let mut new_clique = Bitvec::from_vector(&vertii, self.capacity);
-                            new_clique.insert(neighbour);
-                            new_cliques.push(new_clique);
-                        }
-                    }
-                }
-                None => continue,
-            }
-        }
-        new_cliques
+    fn next_cliques_chunked<'a>(
+        &'a self,
+        cliques: &'a CliqueLevel,
+        chunk_size: usize,
+    ) -> LevelChunks<'a> {
+        LevelChunks::new(self, cliques, chunk_size)
     }
 }
 
-/// Creates the boundary matrix given cliques of size N to size N+1
-/// It is the less efficent version of the next clique algorithm since it needs
-/// to account for all N cliques that generate the N+1 one. Also has to use a hashmap
-/// to store these repetitions with takes away from the efficiency of Nimbus.
-/// A bit of a defeat nonetheless.
-pub trait BoundaryMatrix {
-    fn boundary_matrix(&self, cliques: &[Bitvec]) -> (Vec<Bitvec>, Vec<Bitvec>);
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::{visit_levels, LEVEL_CHUNK};
+    use crate::limits::{LimitedCliques, Limits, StopReason};
+    use crate::search::{PivotStrategy, SearchStats};
+    use crate::testutil::{graph_from_edges, maximal_cliques};
+    use std::ops::ControlFlow;
 
-impl BoundaryMatrix for Bitmatrix {
-    fn boundary_matrix(&self, cliques: &[Bitvec]) -> (Vec<Bitvec>, Vec<Bitvec>) {
-        let mut clique_map: HashMap<Vec<usize>, Vec<usize>> = HashMap::new();
-        let degrees: Vec<usize> = self.matrix.iter().map(|row| row.n_elements()).collect();
-        for (index, clique) in cliques.iter().enumerate() {
-            let clique_size = clique.n_elements();
-            let vertii = clique.elements();
-            //get common neighbours of cliques
-            let mut common_neighbours: Bitvec;
-            match vertii.first() {
-                Some(first) => {
-                    common_neighbours = self.matrix[*first].clone();
-                    for vertex in vertii.iter().skip(1) {
-                        common_neighbours.intersection_with(&self.matrix[*vertex]);
-                    }
-                }
-                None => continue,
-            }
-            for neighbour in common_neighbours.elements().into_iter() {
-                if degrees[neighbour] < clique_size {
-                    continue;
-                }
-                if self.matrix[neighbour].contains_all(&vertii) {
-                    let mut element = vertii.clone();
-                    element.push(neighbour);
-                    element.sort();
-                    let entry = clique_map.entry(element).or_insert(Vec::new());
-                    entry.push(index);
-                }
+    #[test]
+    fn test_maximal_cliques() {
+        // two triangles sharing the edge 1-2, a pendant vertex and an isolated one
+        let graph = graph_from_edges(&[(0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (3, 4)], 6);
+        let mut cliques = maximal_cliques(&graph);
+        cliques.sort();
+        assert_eq!(
+            cliques,
+            vec![vec![0, 1, 2], vec![1, 2, 3], vec![3, 4], vec![5]]
+        );
+        let histogram = cliques.iter().fold(Vec::new(), |mut histogram, clique| {
+            add_to_histogram(&mut histogram, clique.len());
+            histogram
+        });
+        assert_eq!(histogram, vec![1, 1, 2]);
+        assert_eq!(merge_histograms(histogram, vec![2]), vec![3, 1, 2]);
+    }
+    #[test]
+    fn test_f_vector() {
+        // K5 and a disjoint triangle spread over more than one byte of vertices
+        let mut edges = Vec::new();
+        for u in 0..5 {
+            for v in u + 1..5 {
+                edges.push((u, v));
             }
         }
-        let mut new_cliques = Vec::new();
-        let mut matrix = Vec::new();
-        for (key, value) in clique_map.into_iter() {
-            //add the n+1 cliques
-            new_cliques.push(Bitvec::from_vector(&key, self.capacity));
-            //add the matrix
-            matrix.push(Bitvec::from_vector(&value, cliques.len()));
+        edges.extend([(9, 10), (9, 12), (10, 12)]);
+        let graph = graph_from_edges(&edges, 13);
+        let mut f_vector = vec![0; 5];
+        let _ = visit_levels(&graph, 4, &mut SearchStats::default(), &mut |chunk, _| {
+            f_vector[chunk.size() - 1] += chunk.len();
+            ControlFlow::Continue(())
+        });
+        assert_eq!(f_vector, vec![13, 13, 11, 5, 1]);
+        let mut edges = CliqueLevel::new(2);
+        for mut chunk in graph.next_cliques_chunked(&CliqueLevel::singletons(13), 4) {
+            edges.append(&mut chunk);
         }
-        (new_cliques, matrix)
+        assert_eq!(edges.len(), 13);
+        let triangles = graph.next_cliques_chunked(&edges, LEVEL_CHUNK);
+        assert_eq!(triangles.map(|chunk| chunk.len()).sum::<usize>(), 11);
+    }
+    #[test]
+    fn test_visit_cliques_stops() {
+        let graph = graph_from_edges(&[(0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (3, 4)], 6);
+        let mut seen = 0;
+        let outcome = graph.visit_cliques_limited(
+            false,
+            PivotStrategy::default(),
+            &Limits::default(),
+            &mut || false,
+            &mut |_: &[usize]| {
+                seen += 1;
+                if seen == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        assert_eq!(outcome.stopped, Some(StopReason::Visitor));
+        assert_eq!((seen, outcome.results), (2, 1));
+    }
+    #[test]
+    fn test_maximal_cliques_empty() {
+        let graph = graph_from_edges(&[], 0);
+        assert!(maximal_cliques(&graph).is_empty());
     }
 }
@@ -7,7 +7,7 @@ pub struct Bitvec(Vec<u8>);
 
 impl Bitvec {
     pub fn new(capacity: usize) -> Self {
-        let n_bytes: usize = (capacity + 7) / 8;
+        let n_bytes: usize = capacity.div_ceil(8);
         Bitvec(vec![0; n_bytes])
     }
 
@@ -19,6 +19,11 @@ impl Bitvec {
         bitvec
     }
 
+    /// Rebuilds a bitvec from the bytes `iter` yields.
+    pub fn from_bytes(bytes: Vec<u8>) -> Bitvec {
+        Bitvec(bytes)
+    }
+
     pub fn iter(&self) -> Iter<'_, u8> {
         self.0.iter()
     }
@@ -48,11 +53,6 @@ impl Bitvec {
             panic!("Index out of bounds");
         }
     }
-    pub fn insertion(&self, index: usize) -> Bitvec {
-        let mut away = self.clone();
-        away.insert(index);
-        away
-    }
     pub fn remove(&mut self, index: usize) {
         let byte_position = index / 8;
         let bit_position = index % 8;
@@ -65,12 +65,6 @@ impl Bitvec {
         }
     }
 
-    pub fn removal(&self, index: usize) -> Bitvec {
-        let mut away = self.clone();
-        away.remove(index);
-        away
-    }
-
     pub fn contains(&self, index: usize) -> bool {
         let byte_position = index / 8;
         let bit_position = index % 8;
@@ -91,7 +85,7 @@ impl Bitvec {
                 return false;
             }
         }
-        return true;
+        true
     }
 
     pub fn intersection(&self, other: &Bitvec) -> Bitvec {
@@ -106,17 +100,13 @@ impl Bitvec {
 
         Bitvec(result_bytes)
     }
-    pub fn union(&self, other: &Bitvec) -> Bitvec {
+    pub fn intersection_count(&self, other: &Bitvec) -> usize {
         // Ensure both Nimbus instances have the same length
         assert_eq!(self.len(), other.len(), "Vectors must have the same length");
-
-        let result_bytes: Vec<u8> = self
-            .iter()
+        self.iter()
             .zip(&other.0)
-            .map(|(&byte_self, &byte_other)| byte_self | byte_other)
-            .collect();
-
-        Bitvec(result_bytes)
+            .map(|(&byte_self, &byte_other)| (byte_self & byte_other).count_ones() as usize)
+            .sum()
     }
 
     pub fn difference(&self, other: &Bitvec) -> Bitvec {
@@ -133,6 +123,19 @@ impl Bitvec {
         result
     }
 
+    pub fn difference_with(&mut self, other: &Bitvec) {
+        // Ensure both Nimbus instances have the same length
+        assert_eq!(
+            self.0.len(),
+            other.0.len(),
+            "Vectors must have the same length"
+        );
+
+        for (byte_self, &byte_other) in self.iter_mut().zip(&other.0) {
+            *byte_self &= !byte_other;
+        }
+    }
+
     pub fn intersection_with(&mut self, other: &Bitvec) {
         // Ensure both Nimbus instances have the same length
         assert_eq!(
@@ -163,8 +166,11 @@ impl Bitvec {
         let mut offset = 0;
 
         for byte in self.iter() {
-            let byte_positions = find_set_bits_positions_in_byte(*byte);
-            positions.extend(byte_positions.iter().map(|pos| pos + offset));
+            // sparse rows are mostly zero bytes, which hold nothing to collect
+            if *byte != 0 {
+                let byte_positions = find_set_bits_positions_in_byte(*byte);
+                positions.extend(byte_positions.iter().map(|pos| pos + offset));
+            }
             offset += 8; // Move the offset to the next byte position
         }
 
@@ -173,7 +179,7 @@ impl Bitvec {
 
     pub fn elements_from(&self, start_index: usize) -> Vec<usize> {
         let mut positions = Vec::new();
-        let mut offset = start_index / 8;
+        let mut offset = (start_index / 8) * 8;
 
         for byte in self.iter().skip(start_index / 8) {
             let byte_positions = find_set_bits_positions_in_byte(*byte);
@@ -251,22 +257,6 @@ mod tests {
         assert_eq!(format!("{}", bitvec), "00000100 00000001");
     }
     #[test]
-    fn test_insertion_removal() {
-        let mut bitvec = Bitvec::new(16);
-        assert_eq!(format!("{}", bitvec), "00000000 00000000");
-        // Insert some bits
-        bitvec.insert(5);
-        let bitvec2 = bitvec.insertion(10);
-        assert_eq!(format!("{}", bitvec), "00000100 00000000");
-        assert_eq!(format!("{}", bitvec2), "00000100 00100000");
-        bitvec.insert(10);
-        bitvec.insert(15);
-        // Remove a bit
-        let bitvec3 = bitvec.removal(15);
-        assert_eq!(format!("{}", bitvec3), "00000100 00100000");
-        assert_eq!(format!("{}", bitvec), "00000100 00100001");
-    }
-    #[test]
     fn test_contains() {
         let mut bitvec = Bitvec::new(16);
         assert!(!bitvec.contains(5)); // Empty Nimbus, bit at index 5 should not be contained
@@ -278,7 +268,7 @@ mod tests {
         assert!(bitvec.contains_all(&[5, 10]))
     }
     #[test]
-    fn test_union_and_intersection() {
+    fn test_intersection() {
         let mut bitvec1 = Bitvec::new(16);
         let mut bitvec2 = Bitvec::new(16);
 
@@ -287,11 +277,15 @@ mod tests {
         bitvec2.insert(10);
         bitvec2.insert(15);
 
-        let union_bitvec = bitvec1.union(&bitvec2);
-        assert_eq!(format!("{}", union_bitvec), "00000100 00100001");
-
-        let union_bitvec = bitvec1.intersection(&bitvec2);
-        assert_eq!(format!("{}", union_bitvec), "00000000 00100000");
+        let intersection = bitvec1.intersection(&bitvec2);
+        assert_eq!(format!("{}", intersection), "00000000 00100000");
+    }
+    #[test]
+    fn test_intersection_count() {
+        let bitvec1 = Bitvec::from_vector(&[3, 4, 10, 12], 16);
+        let bitvec2 = Bitvec::from_vector(&[4, 5, 10, 15], 16);
+        assert_eq!(bitvec1.intersection_count(&bitvec2), 2);
+        assert_eq!(bitvec1.intersection_count(&Bitvec::new(16)), 0);
     }
     #[test]
     fn test_union_and_intersection_inplace() {
@@ -320,35 +314,47 @@ mod tests {
     }
     #[test]
     fn test_from_vector() {
-        let bitvec = Bitvec::from_vector(&vec![3, 4, 12, 6], 16);
+        let bitvec = Bitvec::from_vector(&[3, 4, 12, 6], 16);
         assert_eq!(bitvec.elements(), vec![3, 4, 6, 12]);
     }
     #[test]
     fn test_n_elements() {
-        let bitvec = Bitvec::from_vector(&vec![3, 4, 12, 6], 16);
+        let bitvec = Bitvec::from_vector(&[3, 4, 12, 6], 16);
         assert_eq!(bitvec.n_elements(), 4);
     }
     #[test]
     fn test_empty() {
-        let bitvec = Bitvec::from_vector(&vec![3, 4, 12, 6], 16);
+        let bitvec = Bitvec::from_vector(&[3, 4, 12, 6], 16);
         assert!(!bitvec.is_empty());
-        let bitvec = Bitvec::from_vector(&vec![], 16);
+        let bitvec = Bitvec::from_vector(&[], 16);
         assert!(bitvec.is_empty());
     }
     #[test]
     fn test_difference() {
-        let bitvec1 = Bitvec::from_vector(&vec![3, 4, 12, 6], 16);
-        let bitvec2 = Bitvec::from_vector(&vec![4, 5], 16);
+        let bitvec1 = Bitvec::from_vector(&[3, 4, 12, 6], 16);
+        let bitvec2 = Bitvec::from_vector(&[4, 5], 16);
         assert_eq!(bitvec1.difference(&bitvec2).elements(), vec![3, 6, 12]);
+        let mut bitvec3 = bitvec1.clone();
+        bitvec3.difference_with(&bitvec2);
+        assert_eq!(bitvec3.elements(), vec![3, 6, 12]);
     }
     #[test]
     fn test_first_element() {
-        let bitvec = Bitvec::from_vector(&vec![5, 4, 12, 6], 16);
+        let bitvec = Bitvec::from_vector(&[5, 4, 12, 6], 16);
         assert_eq!(bitvec.first_index(), Some(4));
     }
     #[test]
     fn test_elements_from() {
-        let bitvec = Bitvec::from_vector(&vec![5, 4, 12, 6], 16);
+        let bitvec = Bitvec::from_vector(&[5, 4, 12, 6], 16);
         assert_eq!(bitvec.elements_from(5), vec![6, 12]);
     }
+    #[test]
+    fn test_elements_from_unaligned() {
+        // starts past the first byte must keep positions in bits, not bytes
+        let bitvec = Bitvec::from_vector(&[2, 9, 13, 14, 20, 23], 24);
+        assert_eq!(bitvec.elements_from(9), vec![13, 14, 20, 23]);
+        assert_eq!(bitvec.elements_from(13), vec![14, 20, 23]);
+        assert_eq!(bitvec.elements_from(17), vec![20, 23]);
+        assert_eq!(bitvec.elements_from(23), vec![]);
+    }
 }
@@ -0,0 +1,483 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::kclique::{count_k_cliques_from, visit_k_cliques_from};
+use crate::limits::CHECK_INTERVAL;
+use crate::search::{CliqueSearch, Step};
+use crate::visitor::CliqueVisitor;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 8] = b"HERONCKP";
+const VERSION: u64 = 1;
+// what the saved state belongs to, so one kind of run cannot pick up another's file
+const MAXIMAL_CLIQUES: u64 = 1;
+const K_CLIQUES: u64 = 2;
+
+/// FNV-1a hash of the vertex count and adjacency bitsets, stored in every checkpoint so it
+/// is only ever resumed against the graph it was taken on.
+pub fn fingerprint(graph: &Bitmatrix) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |byte: u8| {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    };
+    for byte in (graph.n_nodes() as u64).to_le_bytes() {
+        feed(byte);
+    }
+    for row in &graph.matrix {
+        for &byte in row.iter() {
+            feed(byte);
+        }
+    }
+    hash
+}
+
+/// Serialises search state as little-endian words.
+pub(crate) struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    fn new() -> Self {
+        StateWriter(Vec::new())
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub(crate) fn usizes(&mut self, values: &[usize]) {
+        self.usize(values.len());
+        for &value in values {
+            self.usize(value);
+        }
+    }
+
+    pub(crate) fn bitvec(&mut self, bitvec: &Bitvec) {
+        self.usize(bitvec.len());
+        self.0.extend(bitvec.iter());
+    }
+}
+
+/// Reads back what a `StateWriter` wrote, failing with `InvalidData` on anything malformed.
+pub(crate) struct StateReader<'a>(&'a [u8]);
+
+impl StateReader<'_> {
+    pub(crate) fn invalid(message: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+
+    fn take(&mut self, n_bytes: usize) -> io::Result<&[u8]> {
+        if self.0.len() < n_bytes {
+            return Err(Self::invalid("truncated checkpoint".to_string()));
+        }
+        let (head, tail) = self.0.split_at(n_bytes);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<u64> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub(crate) fn usize(&mut self) -> io::Result<usize> {
+        let value = self.u64()?;
+        usize::try_from(value).map_err(|_| Self::invalid(format!("value {} too large", value)))
+    }
+
+    pub(crate) fn usizes(&mut self) -> io::Result<Vec<usize>> {
+        let len = self.usize()?;
+        if len > self.0.len() / 8 {
+            return Err(Self::invalid("truncated checkpoint".to_string()));
+        }
+        (0..len).map(|_| self.usize()).collect()
+    }
+
+    /// A bitvec over `capacity` elements.
+    pub(crate) fn bitvec(&mut self, capacity: usize) -> io::Result<Bitvec> {
+        let n_bytes = self.usize()?;
+        if n_bytes != capacity.div_ceil(8) {
+            return Err(Self::invalid("bitset of the wrong size".to_string()));
+        }
+        Ok(Bitvec::from_bytes(self.take(n_bytes)?.to_vec()))
+    }
+}
+
+/// Writes `state` under a header naming its kind and graph. The file is written next to
+/// `path` and renamed over it, so a run killed mid-write leaves the previous checkpoint.
+fn write_checkpoint(
+    path: &Path,
+    kind: u64,
+    fingerprint: u64,
+    state: StateWriter,
+) -> io::Result<()> {
+    let mut header = StateWriter::new();
+    header.0.extend_from_slice(MAGIC);
+    header.u64(VERSION);
+    header.u64(kind);
+    header.u64(fingerprint);
+    let mut temporary = OsString::from(path);
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(&header.0)?;
+    file.write_all(&state.0)?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)
+}
+
+/// Reads a checkpoint file, checking its header, and returns the state it holds.
+fn read_checkpoint(path: &Path, kind: u64, graph: &Bitmatrix) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if !bytes.starts_with(MAGIC) {
+        return Err(StateReader::invalid("not a checkpoint file".to_string()));
+    }
+    let mut input = StateReader(&bytes[MAGIC.len()..]);
+    let version = input.u64()?;
+    if version != VERSION {
+        return Err(StateReader::invalid(format!(
+            "unsupported checkpoint version {}",
+            version
+        )));
+    }
+    if input.u64()? != kind {
+        return Err(StateReader::invalid(
+            "checkpoint was taken by a different kind of run".to_string(),
+        ));
+    }
+    if input.u64()? != fingerprint(graph) {
+        return Err(StateReader::invalid(
+            "checkpoint was taken on a different graph".to_string(),
+        ));
+    }
+    Ok(input.0.to_vec())
+}
+
+/// Saved progress of a maximal clique enumeration: the search state and the number of
+/// cliques reported before it.
+pub struct CliqueCheckpoint {
+    fingerprint: u64,
+    pub search: CliqueSearch,
+    pub found: u64,
+}
+
+impl CliqueCheckpoint {
+    pub fn new(graph: &Bitmatrix, search: CliqueSearch) -> Self {
+        CliqueCheckpoint {
+            fingerprint: fingerprint(graph),
+            search,
+            found: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.search.is_exhausted()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut state = StateWriter::new();
+        state.u64(self.found);
+        self.search.write_state(&mut state);
+        write_checkpoint(path, MAXIMAL_CLIQUES, self.fingerprint, state)
+    }
+
+    /// Loads a checkpoint saved by `save`, failing with `InvalidData` if it was taken on
+    /// another graph.
+    pub fn load(graph: &Bitmatrix, path: &Path) -> io::Result<Self> {
+        let bytes = read_checkpoint(path, MAXIMAL_CLIQUES, graph)?;
+        let mut input = StateReader(&bytes);
+        let found = input.u64()?;
+        let search = CliqueSearch::read_state(&mut input, graph.n_nodes())?;
+        Ok(CliqueCheckpoint {
+            fingerprint: fingerprint(graph),
+            search,
+            found,
+        })
+    }
+}
+
+/// Saved progress of a k-clique run. Work is split by the first vertex of each clique
+/// along the degeneracy ordering, and only whole vertices are saved: `next_vertex` is the
+/// first one still to do and `found` counts the cliques of those before it.
+pub struct KCliqueCheckpoint {
+    fingerprint: u64,
+    pub k: usize,
+    pub next_vertex: usize,
+    pub found: u64,
+}
+
+impl KCliqueCheckpoint {
+    pub fn new(graph: &Bitmatrix, k: usize) -> Self {
+        KCliqueCheckpoint {
+            fingerprint: fingerprint(graph),
+            k,
+            next_vertex: 0,
+            found: 0,
+        }
+    }
+
+    pub fn is_finished(&self, graph: &Bitmatrix) -> bool {
+        self.k == 0 || self.next_vertex >= graph.n_nodes()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut state = StateWriter::new();
+        state.usize(self.k);
+        state.usize(self.next_vertex);
+        state.u64(self.found);
+        write_checkpoint(path, K_CLIQUES, self.fingerprint, state)
+    }
+
+    /// Loads a checkpoint saved by `save`, failing with `InvalidData` if it was taken on
+    /// another graph.
+    pub fn load(graph: &Bitmatrix, path: &Path) -> io::Result<Self> {
+        let bytes = read_checkpoint(path, K_CLIQUES, graph)?;
+        let mut input = StateReader(&bytes);
+        Ok(KCliqueCheckpoint {
+            fingerprint: fingerprint(graph),
+            k: input.usize()?,
+            next_vertex: input.usize()?,
+            found: input.u64()?,
+        })
+    }
+}
+
+/// When a resumable run saves its progress to `path`: every `interval`, whenever
+/// `request` is set (it is cleared again once saved), and before returning. `interrupt`
+/// is polled alongside and stops the run.
+pub struct CheckpointPolicy<'a> {
+    pub path: PathBuf,
+    pub interval: Option<Duration>,
+    pub request: Option<&'a AtomicBool>,
+    pub interrupt: &'a mut dyn FnMut() -> bool,
+}
+
+impl CheckpointPolicy<'_> {
+    fn save_due(&self, last_save: Instant) -> bool {
+        let requested = self
+            .request
+            .is_some_and(|request| request.swap(false, Ordering::Relaxed));
+        requested
+            || self
+                .interval
+                .is_some_and(|interval| last_save.elapsed() >= interval)
+    }
+}
+
+/// Enumerations that can be stopped, saved and picked up again later, possibly by another
+/// process. Each returns `ControlFlow::Break` if the visitor or the interrupt stopped it,
+/// with the progress so far saved; cliques visited after the last save are visited again
+/// when the run resumes from that save.
+pub trait Resumable {
+    /// Carries on the maximal clique enumeration held in `state`.
+    fn resume_cliques<V: CliqueVisitor>(
+        &self,
+        state: &mut CliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+        visitor: &mut V,
+    ) -> io::Result<ControlFlow<()>>;
+    /// Carries on visiting the k-cliques of `state`. A run stopped part-way through a
+    /// vertex resumes from the start of that vertex.
+    fn resume_k_cliques<V: CliqueVisitor>(
+        &self,
+        state: &mut KCliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+        visitor: &mut V,
+    ) -> io::Result<ControlFlow<()>>;
+    /// Carries on counting the k-cliques of `state`, which ends with the total in
+    /// `state.found`. Stops are only noticed between vertices.
+    fn resume_count_k_cliques(
+        &self,
+        state: &mut KCliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+    ) -> io::Result<ControlFlow<()>>;
+}
+
+impl Bitmatrix {
+    /// Shared driver of the k-clique runs: `work(v)` handles the cliques starting at `v`
+    /// and returns how many there were, or `None` if it was stopped.
+    fn resume_by_vertex(
+        &self,
+        state: &mut KCliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+        work: &mut dyn FnMut(usize, &[usize]) -> Option<u64>,
+    ) -> io::Result<ControlFlow<()>> {
+        let ordering = self.degeneracy_ordering();
+        let mut last_save = Instant::now();
+        while !state.is_finished(self) {
+            if (policy.interrupt)() {
+                state.save(&policy.path)?;
+                return Ok(ControlFlow::Break(()));
+            }
+            match work(state.next_vertex, &ordering.position) {
+                Some(count) => {
+                    state.found += count;
+                    state.next_vertex += 1;
+                }
+                None => {
+                    state.save(&policy.path)?;
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+            if policy.save_due(last_save) {
+                state.save(&policy.path)?;
+                last_save = Instant::now();
+            }
+        }
+        state.save(&policy.path)?;
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+impl Resumable for Bitmatrix {
+    fn resume_cliques<V: CliqueVisitor>(
+        &self,
+        state: &mut CliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+        visitor: &mut V,
+    ) -> io::Result<ControlFlow<()>> {
+        let mut last_save = Instant::now();
+        loop {
+            match state.search.step(self, CHECK_INTERVAL) {
+                Step::Clique(clique) => {
+                    state.found += 1;
+                    if visitor.visit(clique).is_break() {
+                        state.save(&policy.path)?;
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+                Step::Paused => {
+                    if (policy.interrupt)() {
+                        state.save(&policy.path)?;
+                        return Ok(ControlFlow::Break(()));
+                    }
+                    if policy.save_due(last_save) {
+                        state.save(&policy.path)?;
+                        last_save = Instant::now();
+                    }
+                }
+                Step::Exhausted => {
+                    state.save(&policy.path)?;
+                    return Ok(ControlFlow::Continue(()));
+                }
+            }
+        }
+    }
+
+    fn resume_k_cliques<V: CliqueVisitor>(
+        &self,
+        state: &mut KCliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+        visitor: &mut V,
+    ) -> io::Result<ControlFlow<()>> {
+        let k = state.k;
+        self.resume_by_vertex(state, policy, &mut |v, position| {
+            let mut count = 0;
+            let flow = visit_k_cliques_from(self, v, k, position, &mut |clique: &[usize]| {
+                count += 1;
+                visitor.visit(clique)
+            });
+            flow.is_continue().then_some(count)
+        })
+    }
+
+    fn resume_count_k_cliques(
+        &self,
+        state: &mut KCliqueCheckpoint,
+        policy: &mut CheckpointPolicy,
+    ) -> io::Result<ControlFlow<()>> {
+        let k = state.k;
+        self.resume_by_vertex(state, policy, &mut |v, position| {
+            Some(match k {
+                1 => 1,
+                _ => count_k_cliques_from(self, v, k, position),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kclique::KCliques;
+    use crate::search::PivotStrategy;
+    use crate::testutil::{maximal_cliques, pseudo_random_graph};
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let n_nodes = 40;
+        let graph = pseudo_random_graph(n_nodes, 3, 5, 3);
+        let path = std::env::temp_dir().join(format!("heron-checkpoint-{}", std::process::id()));
+
+        // stop after every few cliques, reloading from disk each time
+        let search = CliqueSearch::new(&graph, PivotStrategy::Random(3));
+        CliqueCheckpoint::new(&graph, search).save(&path).unwrap();
+        let mut found = Vec::new();
+        loop {
+            let mut state = CliqueCheckpoint::load(&graph, &path).unwrap();
+            let mut policy = CheckpointPolicy {
+                path: path.clone(),
+                interval: None,
+                request: None,
+                interrupt: &mut || false,
+            };
+            let mut budget = 7;
+            let flow = graph
+                .resume_cliques(&mut state, &mut policy, &mut |clique: &[usize]| {
+                    let mut clique = clique.to_vec();
+                    clique.sort_unstable();
+                    found.push(clique);
+                    budget -= 1;
+                    match budget {
+                        0 => ControlFlow::Break(()),
+                        _ => ControlFlow::Continue(()),
+                    }
+                })
+                .unwrap();
+            assert_eq!(state.found, found.len() as u64);
+            if flow.is_continue() {
+                assert!(state.is_finished());
+                break;
+            }
+        }
+        found.sort();
+        let mut expected = maximal_cliques(&graph);
+        expected.sort();
+        assert_eq!(found, expected);
+
+        // k-cliques counted a few vertices at a time
+        let mut state = KCliqueCheckpoint::new(&graph, 4);
+        let mut polls = 0;
+        while !state.is_finished(&graph) {
+            let mut interrupt = || {
+                polls += 1;
+                polls % 5 == 0
+            };
+            let mut policy = CheckpointPolicy {
+                path: path.clone(),
+                interval: None,
+                request: None,
+                interrupt: &mut interrupt,
+            };
+            let _ = graph
+                .resume_count_k_cliques(&mut state, &mut policy)
+                .unwrap();
+            state = KCliqueCheckpoint::load(&graph, &path).unwrap();
+        }
+        assert_eq!(state.found, graph.count_k_cliques(4));
+
+        let other = graph.complement();
+        let error = KCliqueCheckpoint::load(&other, &path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(CliqueCheckpoint::load(&graph, &path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,453 @@
+use crate::visitor::CliqueVisitor;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"HERONCLQ";
+const TEXT_MAGIC: &str = "# heron cliques";
+const VERSION: u64 = 1;
+
+/// How cliques are laid out after the header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CliqueFormat {
+    /// Per clique, varints for its size, its smallest vertex, and the gaps between
+    /// consecutive vertices in increasing order.
+    Binary,
+    /// One line per clique, vertices in increasing order separated by spaces.
+    Text,
+}
+
+/// What a clique file describes: the graph's vertex count and the enumeration parameters
+/// (algorithm, size bounds...) as key/value pairs, in the order they were given.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CliqueFileHeader {
+    pub n_nodes: usize,
+    pub params: Vec<(String, String)>,
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// LEB128 varint at `bytes[*pos..]`, advancing `pos` past it.
+fn get_varint(bytes: &[u8], pos: &mut usize) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| invalid("truncated clique file"))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+fn get_usize(bytes: &[u8], pos: &mut usize) -> io::Result<usize> {
+    usize::try_from(get_varint(bytes, pos)?).map_err(|_| invalid("value too large"))
+}
+
+fn get_string(bytes: &[u8], pos: &mut usize) -> io::Result<String> {
+    let len = get_usize(bytes, pos)?;
+    let end = pos
+        .checked_add(len)
+        .filter(|&end| end <= bytes.len())
+        .ok_or_else(|| invalid("truncated clique file"))?;
+    let string = std::str::from_utf8(&bytes[*pos..end]).map_err(|_| invalid("invalid UTF-8"))?;
+    *pos = end;
+    Ok(string.to_string())
+}
+
+/// Cliques encoded but not yet written. Parallel workers each fill their own buffer and
+/// only take turns on the shared `CliqueWriter` to append a full one.
+#[derive(Default)]
+pub struct CliqueBuffer {
+    bytes: Vec<u8>,
+    count: u64,
+    // the clique being encoded, sorted
+    sorted: Vec<usize>,
+}
+
+impl CliqueBuffer {
+    pub fn push(&mut self, format: CliqueFormat, clique: &[usize]) {
+        self.sorted.clear();
+        self.sorted.extend_from_slice(clique);
+        self.sorted.sort_unstable();
+        match format {
+            CliqueFormat::Binary => {
+                put_varint(&mut self.bytes, self.sorted.len() as u64);
+                let mut previous = 0;
+                for &v in &self.sorted {
+                    put_varint(&mut self.bytes, (v - previous) as u64);
+                    previous = v;
+                }
+            }
+            CliqueFormat::Text => {
+                for (i, v) in self.sorted.iter().enumerate() {
+                    if i > 0 {
+                        self.bytes.push(b' ');
+                    }
+                    self.bytes.extend_from_slice(v.to_string().as_bytes());
+                }
+                self.bytes.push(b'\n');
+            }
+        }
+        self.count += 1;
+    }
+
+    /// Size of the encoded cliques.
+    pub fn n_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Moves the cliques of `other` to the end of this buffer.
+    pub fn append(&mut self, other: &mut CliqueBuffer) {
+        self.bytes.append(&mut other.bytes);
+        self.count += std::mem::take(&mut other.count);
+    }
+}
+
+/// Streams cliques to `W` as they are found, so their number is only bounded by the disk.
+/// Also a `CliqueVisitor`: the first write error stops the enumeration and is kept in
+/// `error` (`finish` returns it).
+pub struct CliqueWriter<W: Write> {
+    inner: W,
+    format: CliqueFormat,
+    buffer: CliqueBuffer,
+    count: u64,
+    pub error: Option<io::Error>,
+}
+
+impl CliqueWriter<BufWriter<File>> {
+    pub fn create(
+        path: &Path,
+        header: &CliqueFileHeader,
+        format: CliqueFormat,
+    ) -> io::Result<Self> {
+        CliqueWriter::new(BufWriter::new(File::create(path)?), header, format)
+    }
+}
+
+impl<W: Write> CliqueWriter<W> {
+    pub fn new(mut inner: W, header: &CliqueFileHeader, format: CliqueFormat) -> io::Result<Self> {
+        match format {
+            CliqueFormat::Binary => {
+                let mut bytes = MAGIC.to_vec();
+                put_varint(&mut bytes, VERSION);
+                put_varint(&mut bytes, header.n_nodes as u64);
+                put_varint(&mut bytes, header.params.len() as u64);
+                for (key, value) in &header.params {
+                    for string in [key, value] {
+                        put_varint(&mut bytes, string.len() as u64);
+                        bytes.extend_from_slice(string.as_bytes());
+                    }
+                }
+                inner.write_all(&bytes)?;
+            }
+            CliqueFormat::Text => {
+                writeln!(inner, "{} v{}", TEXT_MAGIC, VERSION)?;
+                writeln!(inner, "# n_nodes {}", header.n_nodes)?;
+                for (key, value) in &header.params {
+                    if key.contains(char::is_whitespace) || value.contains('\n') {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("parameter '{}' cannot be written as text", key),
+                        ));
+                    }
+                    writeln!(inner, "# {} {}", key, value)?;
+                }
+            }
+        }
+        Ok(CliqueWriter {
+            inner,
+            format,
+            buffer: CliqueBuffer::default(),
+            count: 0,
+            error: None,
+        })
+    }
+
+    pub fn write(&mut self, clique: &[usize]) -> io::Result<()> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.push(self.format, clique);
+        let written = self.write_buffer(&mut buffer);
+        self.buffer = buffer;
+        written
+    }
+
+    /// Writes out and empties a buffer filled in this writer's format.
+    pub fn write_buffer(&mut self, buffer: &mut CliqueBuffer) -> io::Result<()> {
+        let written = self.inner.write_all(&buffer.bytes);
+        if written.is_ok() {
+            self.count += buffer.count;
+        }
+        buffer.bytes.clear();
+        buffer.count = 0;
+        written
+    }
+
+    /// `write_buffer` for use inside an enumeration: like `visit`, an error is kept in
+    /// `error` and stops the enumeration.
+    pub fn visit_buffer(&mut self, buffer: &mut CliqueBuffer) -> ControlFlow<()> {
+        match self.write_buffer(buffer) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(err) => {
+                self.error.get_or_insert(err);
+                ControlFlow::Break(())
+            }
+        }
+    }
+
+    /// Cliques written so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Flushes the output and hands it back, or returns the error that stopped writing.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> CliqueVisitor for CliqueWriter<W> {
+    fn visit(&mut self, clique: &[usize]) -> ControlFlow<()> {
+        match self.write(clique) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(err) => {
+                self.error.get_or_insert(err);
+                ControlFlow::Break(())
+            }
+        }
+    }
+}
+
+/// A clique file opened for reading. The file is memory-mapped rather than read in, so
+/// it can be far larger than memory; cliques are decoded on the fly by `iter` or
+/// `read_at`.
+pub struct CliqueFile {
+    map: Mmap,
+    pub header: CliqueFileHeader,
+    pub format: CliqueFormat,
+    // offset of the first clique
+    start: usize,
+}
+
+impl CliqueFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: the map is only ever read, and the file must not be modified while open
+        let map = unsafe { Mmap::map(&file)? };
+        let (header, format, start) = if map.starts_with(MAGIC) {
+            Self::binary_header(&map)?
+        } else if map.starts_with(TEXT_MAGIC.as_bytes()) {
+            Self::text_header(&map)?
+        } else {
+            return Err(invalid("not a clique file"));
+        };
+        Ok(CliqueFile {
+            map,
+            header,
+            format,
+            start,
+        })
+    }
+
+    fn binary_header(bytes: &[u8]) -> io::Result<(CliqueFileHeader, CliqueFormat, usize)> {
+        let mut pos = MAGIC.len();
+        if get_varint(bytes, &mut pos)? != VERSION {
+            return Err(invalid("unsupported clique file version"));
+        }
+        let n_nodes = get_usize(bytes, &mut pos)?;
+        let n_params = get_usize(bytes, &mut pos)?;
+        let mut params = Vec::new();
+        for _ in 0..n_params {
+            params.push((get_string(bytes, &mut pos)?, get_string(bytes, &mut pos)?));
+        }
+        let header = CliqueFileHeader { n_nodes, params };
+        Ok((header, CliqueFormat::Binary, pos))
+    }
+
+    fn text_header(bytes: &[u8]) -> io::Result<(CliqueFileHeader, CliqueFormat, usize)> {
+        let mut header = CliqueFileHeader::default();
+        let mut pos = 0;
+        let mut first = true;
+        while bytes.get(pos) == Some(&b'#') {
+            let end = bytes[pos..]
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .map_or(bytes.len(), |offset| pos + offset);
+            let line =
+                std::str::from_utf8(&bytes[pos..end]).map_err(|_| invalid("invalid UTF-8"))?;
+            pos = (end + 1).min(bytes.len());
+            if first {
+                if line != format!("{} v{}", TEXT_MAGIC, VERSION) {
+                    return Err(invalid("unsupported clique file version"));
+                }
+                first = false;
+                continue;
+            }
+            let (key, value) = line[1..]
+                .trim_start()
+                .split_once(' ')
+                .unwrap_or((&line[1..], ""));
+            match key {
+                "n_nodes" => {
+                    header.n_nodes = value.parse().map_err(|_| invalid("invalid n_nodes"))?
+                }
+                _ => header.params.push((key.to_string(), value.to_string())),
+            }
+        }
+        Ok((header, CliqueFormat::Text, pos))
+    }
+
+    /// Decodes the clique at offset `*pos` into `clique` and moves `pos` to the next one.
+    /// Returns `false` at the end of the file. Offsets start at `start()`.
+    pub fn read_at(&self, pos: &mut usize, clique: &mut Vec<usize>) -> io::Result<bool> {
+        let bytes = &self.map[..];
+        clique.clear();
+        if *pos >= bytes.len() {
+            return Ok(false);
+        }
+        match self.format {
+            CliqueFormat::Binary => {
+                let size = get_usize(bytes, pos)?;
+                let mut v = 0usize;
+                for i in 0..size {
+                    let gap = get_usize(bytes, pos)?;
+                    if i > 0 && gap == 0 {
+                        return Err(invalid("vertices not strictly increasing"));
+                    }
+                    v = v
+                        .checked_add(gap)
+                        .ok_or_else(|| invalid("vertex too large"))?;
+                    clique.push(v);
+                }
+            }
+            CliqueFormat::Text => {
+                let end = bytes[*pos..]
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .map_or(bytes.len(), |offset| *pos + offset);
+                let line =
+                    std::str::from_utf8(&bytes[*pos..end]).map_err(|_| invalid("invalid UTF-8"))?;
+                for word in line.split_ascii_whitespace() {
+                    let v = word.parse().map_err(|_| invalid("invalid vertex"))?;
+                    if clique.last().is_some_and(|&last| last >= v) {
+                        return Err(invalid("vertices not strictly increasing"));
+                    }
+                    clique.push(v);
+                }
+                *pos = (end + 1).min(bytes.len());
+            }
+        }
+        // vertices increase, so checking the last one covers them all
+        if clique.last().is_some_and(|&v| v >= self.header.n_nodes) {
+            return Err(invalid("vertex out of range"));
+        }
+        Ok(true)
+    }
+
+    /// Offset of the first clique, for `read_at`.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Iterator-style `read_at`: the clique at `*pos`, or `None` at the end. A malformed
+    /// record moves `pos` past the end, so the error is reported once and reading stops
+    /// there rather than decoding from the middle of a record.
+    pub fn next_clique(&self, pos: &mut usize) -> Option<io::Result<Vec<usize>>> {
+        let mut clique = Vec::new();
+        match self.read_at(pos, &mut clique) {
+            Ok(true) => Some(Ok(clique)),
+            Ok(false) => None,
+            Err(err) => {
+                *pos = usize::MAX;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::{LimitedCliques, Limits};
+    use crate::search::PivotStrategy;
+    use crate::testutil::{graph_where, maximal_cliques};
+
+    #[test]
+    fn test_clique_file_round_trip() {
+        let n_nodes = 40;
+        let graph = graph_where(n_nodes, |u, v| (u * v + u + v) % 7 < 4 || u + v > 70);
+        let mut expected = maximal_cliques(&graph);
+        expected
+            .iter_mut()
+            .for_each(|clique| clique.sort_unstable());
+        let header = CliqueFileHeader {
+            n_nodes,
+            params: vec![("algorithm".to_string(), "pivot".to_string())],
+        };
+        for format in [CliqueFormat::Binary, CliqueFormat::Text] {
+            let path = std::env::temp_dir().join(format!(
+                "heron-cliques-{}-{:?}",
+                std::process::id(),
+                format
+            ));
+            let mut writer = CliqueWriter::create(&path, &header, format).unwrap();
+            let _ = graph.visit_cliques_limited(
+                false,
+                PivotStrategy::default(),
+                &Limits::default(),
+                &mut || false,
+                &mut writer,
+            );
+            assert_eq!(writer.count(), expected.len() as u64);
+            writer.finish().unwrap();
+
+            let file = CliqueFile::open(&path).unwrap();
+            assert_eq!(file.header, header);
+            assert_eq!(file.format, format);
+            let mut pos = file.start();
+            let cliques: Vec<Vec<usize>> = std::iter::from_fn(|| file.next_clique(&mut pos))
+                .map(|clique| clique.unwrap())
+                .collect();
+            assert_eq!(cliques, expected);
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        // repeated or unsorted vertices are rejected, once, in both formats
+        let binary = [&MAGIC[..], &[1, 5, 0], &[2, 1, 2], &[2, 3, 0], &[1, 4]].concat();
+        let text = format!("{} v1\n# n_nodes 5\n1 3\n3 3\n4\n", TEXT_MAGIC);
+        for (i, contents) in [binary, text.into_bytes()].into_iter().enumerate() {
+            let path = std::env::temp_dir().join(format!(
+                "heron-cliques-{}-invalid-{}",
+                std::process::id(),
+                i
+            ));
+            std::fs::write(&path, contents).unwrap();
+            let file = CliqueFile::open(&path).unwrap();
+            let mut pos = file.start();
+            assert_eq!(file.next_clique(&mut pos).unwrap().unwrap(), vec![1, 3]);
+            let err = file.next_clique(&mut pos).unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(file.next_clique(&mut pos).is_none());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::SparseCliques;
+
+/// Every pair `(i, j, overlap)` with `i < j` of cliques sharing at least `min_overlap`
+/// vertices. Pairs are found through the cliques containing each vertex, so cliques
+/// with nothing in common are never compared.
+pub(crate) fn clique_overlaps(
+    cliques: &[Vec<usize>],
+    n_nodes: usize,
+    min_overlap: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut containing = vec![Vec::new(); n_nodes];
+    for (i, clique) in cliques.iter().enumerate() {
+        for &v in clique {
+            containing[v].push(i);
+        }
+    }
+    let mut overlaps = Vec::new();
+    let mut shared = vec![0; cliques.len()];
+    let mut touched = Vec::new();
+    for (i, clique) in cliques.iter().enumerate() {
+        for &v in clique {
+            // the lists are increasing, so the cliques after `i` are a suffix
+            let later = containing[v].partition_point(|&j| j <= i);
+            for &j in &containing[v][later..] {
+                if shared[j] == 0 {
+                    touched.push(j);
+                }
+                shared[j] += 1;
+            }
+        }
+        for j in touched.drain(..) {
+            if shared[j] >= min_overlap.max(1) {
+                overlaps.push((i, j, shared[j]));
+            }
+            shared[j] = 0;
+        }
+    }
+    overlaps
+}
+
+/// Vertex–clique incidence: `by_vertex[v]` holds the cliques containing `v`.
+pub struct Incidence {
+    pub by_vertex: Vec<Bitvec>,
+}
+
+impl Incidence {
+    pub fn new(cliques: &[Vec<usize>], n_vertices: usize) -> Self {
+        let mut by_vertex = vec![Bitvec::new(cliques.len()); n_vertices];
+        for (c, clique) in cliques.iter().enumerate() {
+            for &v in clique {
+                by_vertex[v].insert(c);
+            }
+        }
+        Incidence { by_vertex }
+    }
+    pub fn cliques_of(&self, v: usize) -> Vec<usize> {
+        self.by_vertex[v].elements()
+    }
+}
+
+/// Graph on the maximal cliques, two of them adjacent when they share at least
+/// `min_overlap` vertices. `overlaps` lists every such pair `(i, j, shared)` with
+/// `i < j`; clique ids index `cliques`.
+pub struct CliqueGraph {
+    pub cliques: Vec<Vec<usize>>,
+    pub graph: Bitmatrix,
+    pub overlaps: Vec<(usize, usize, usize)>,
+}
+
+/// Structures built on top of the maximal clique enumeration. Cliques are sorted lists
+/// of vertices, numbered in sorted order so the ids are stable from run to run.
+pub trait CliqueGraphs {
+    fn vertex_clique_incidence(&self) -> (Vec<Vec<usize>>, Incidence);
+    /// Clique graph keeping only overlaps of at least `min_overlap` vertices (values
+    /// below 1 are treated as 1, the plain intersection graph).
+    fn clique_graph(&self, min_overlap: usize) -> CliqueGraph;
+}
+
+impl Bitmatrix {
+    fn sorted_maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let mut cliques = self.maximal_cliques_sparse();
+        cliques.sort();
+        cliques
+    }
+}
+
+impl CliqueGraphs for Bitmatrix {
+    fn vertex_clique_incidence(&self) -> (Vec<Vec<usize>>, Incidence) {
+        let cliques = self.sorted_maximal_cliques();
+        let incidence = Incidence::new(&cliques, self.n_nodes());
+        (cliques, incidence)
+    }
+
+    fn clique_graph(&self, min_overlap: usize) -> CliqueGraph {
+        let cliques = self.sorted_maximal_cliques();
+        let overlaps = clique_overlaps(&cliques, self.n_nodes(), min_overlap);
+        let mut rows = vec![Vec::new(); cliques.len()];
+        for &(i, j, _) in &overlaps {
+            rows[i].push(j);
+            rows[j].push(i);
+        }
+        let graph = Bitmatrix::new(rows, cliques.len());
+        CliqueGraph {
+            cliques,
+            graph,
+            overlaps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clique_graph() {
+        // two triangles sharing the edge 1-2, a pendant edge 3-4 and an isolated vertex
+        let edges = [(0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (3, 4)];
+        let mut rows = vec![Vec::new(); 6];
+        for &(u, v) in &edges {
+            rows[u].push(v);
+            rows[v].push(u);
+        }
+        let graph = Bitmatrix::new(rows, 6);
+        let (cliques, incidence) = graph.vertex_clique_incidence();
+        assert_eq!(
+            cliques,
+            vec![vec![0, 1, 2], vec![1, 2, 3], vec![3, 4], vec![5]]
+        );
+        let memberships: Vec<Vec<usize>> = (0..6).map(|v| incidence.cliques_of(v)).collect();
+        assert_eq!(
+            memberships,
+            vec![
+                vec![0],
+                vec![0, 1],
+                vec![0, 1],
+                vec![1, 2],
+                vec![2],
+                vec![3]
+            ]
+        );
+
+        let clique_graph = graph.clique_graph(1);
+        assert_eq!(clique_graph.overlaps, vec![(0, 1, 2), (1, 2, 1)]);
+        assert_eq!(clique_graph.graph.get_neighbours(1), vec![0, 2]);
+        assert!(clique_graph.graph.get_neighbours(3).is_empty());
+        let clique_graph = graph.clique_graph(2);
+        assert_eq!(clique_graph.overlaps, vec![(0, 1, 2)]);
+        assert!(clique_graph.graph.get_neighbours(2).is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::limits::Timer;
+use crate::maximum::MaxClique;
+use std::time::Duration;
+
+/// Vertex orders for the greedy colouring.
+#[derive(Clone, Copy, Debug)]
+pub enum ColourOrder {
+    /// Highest degree first (Welsh–Powell).
+    LargestFirst,
+    /// Reverse degeneracy order, which uses at most degeneracy + 1 colours.
+    SmallestLast,
+}
+
+/// Proper vertex colouring: `colours[v]` is the colour of `v`, numbered from 0.
+/// `optimal` is only true when the number of colours is known to be the chromatic
+/// number.
+pub struct Colouring {
+    pub colours: Vec<usize>,
+    pub n_colours: usize,
+    pub optimal: bool,
+}
+
+impl Colouring {
+    fn new(colours: Vec<usize>, optimal: bool) -> Self {
+        let n_colours = colours.iter().map(|&c| c + 1).max().unwrap_or(0);
+        Colouring {
+            colours,
+            n_colours,
+            optimal,
+        }
+    }
+
+    /// The colour classes, as sorted lists of vertices.
+    pub fn classes(&self) -> Vec<Vec<usize>> {
+        let mut classes = vec![Vec::new(); self.n_colours];
+        for (v, &c) in self.colours.iter().enumerate() {
+            classes[c].push(v);
+        }
+        classes
+    }
+}
+
+/// First colour class that has no neighbour of `v`, if any.
+fn free_class(classes: &[Bitvec], neighbours: &Bitvec) -> Option<usize> {
+    classes
+        .iter()
+        .position(|class| class.intersection_count(neighbours) == 0)
+}
+
+/// Uncoloured vertex with the most distinct neighbouring colours, ties going to the one
+/// with most uncoloured neighbours (Brélaz).
+fn most_saturated(graph: &Bitmatrix, classes: &[Bitvec], uncoloured: &Bitvec) -> Option<usize> {
+    uncoloured.elements().into_iter().max_by_key(|&v| {
+        let neighbours = &graph.matrix[v];
+        let saturation = classes
+            .iter()
+            .filter(|class| class.intersection_count(neighbours) > 0)
+            .count();
+        (saturation, neighbours.intersection_count(uncoloured))
+    })
+}
+
+/// Exact colouring by DSATUR branch and bound: always branch on the most saturated
+/// vertex, trying every colour class it fits in and, while that can still beat the
+/// incumbent, a fresh one.
+struct Solver<'a> {
+    graph: &'a Bitmatrix,
+    classes: Vec<Bitvec>,
+    uncoloured: Bitvec,
+    lower_bound: usize,
+    best_n: usize,
+    best: Option<Vec<Bitvec>>,
+    timer: Timer,
+}
+
+impl Solver<'_> {
+    /// Whether the search can stop: the incumbent meets the lower bound or time is up.
+    fn finished(&self) -> bool {
+        self.best_n <= self.lower_bound || self.timer.expired()
+    }
+
+    fn expand(&mut self) {
+        let v = match most_saturated(self.graph, &self.classes, &self.uncoloured) {
+            Some(v) => v,
+            None => {
+                if self.classes.len() < self.best_n {
+                    self.best_n = self.classes.len();
+                    self.best = Some(self.classes.clone());
+                }
+                return;
+            }
+        };
+        if self.timer.tick() {
+            return;
+        }
+        self.uncoloured.remove(v);
+        for c in 0..self.classes.len() {
+            if self.classes[c].intersection_count(&self.graph.matrix[v]) > 0 {
+                continue;
+            }
+            self.classes[c].insert(v);
+            self.expand();
+            self.classes[c].remove(v);
+            if self.finished() {
+                break;
+            }
+        }
+        if !self.finished() && self.classes.len() + 1 < self.best_n {
+            self.classes
+                .push(Bitvec::from_vector(&[v], self.graph.n_nodes()));
+            self.expand();
+            self.classes.pop();
+        }
+        self.uncoloured.insert(v);
+    }
+}
+
+fn colours_from_classes(classes: &[Bitvec], n_nodes: usize) -> Vec<usize> {
+    let mut colours = vec![0; n_nodes];
+    for (c, class) in classes.iter().enumerate() {
+        for v in class.elements() {
+            colours[v] = c;
+        }
+    }
+    colours
+}
+
+/// Vertex colourings on top of the bitset adjacency: colour classes are kept as bitsets,
+/// so checking whether a vertex fits in a class is one intersection.
+pub trait Colour {
+    fn greedy_colouring(&self, order: ColourOrder) -> Colouring;
+    fn dsatur_colouring(&self) -> Colouring;
+    /// Minimum colouring. The search starts from the DSATUR colouring and a maximum
+    /// clique, whose vertices are fixed to distinct colours; with a `time_limit` it may
+    /// stop early, returning the best colouring found with `optimal = false`.
+    fn chromatic_number(&self, time_limit: Option<Duration>) -> Colouring;
+    /// Minimum clique cover, as a minimum colouring of the complement: each colour class
+    /// is a clique here.
+    fn clique_cover(&self, time_limit: Option<Duration>) -> Colouring;
+}
+
+impl Colour for Bitmatrix {
+    fn greedy_colouring(&self, order: ColourOrder) -> Colouring {
+        let n_nodes = self.n_nodes();
+        let order: Vec<usize> = match order {
+            ColourOrder::LargestFirst => {
+                let mut order: Vec<usize> = (0..n_nodes).collect();
+                order.sort_by_key(|&v| std::cmp::Reverse(self.matrix[v].n_elements()));
+                order
+            }
+            ColourOrder::SmallestLast => {
+                let mut order = self.degeneracy_ordering().order;
+                order.reverse();
+                order
+            }
+        };
+        let mut classes: Vec<Bitvec> = Vec::new();
+        let mut colours = vec![0; n_nodes];
+        for v in order {
+            let c = free_class(&classes, &self.matrix[v]).unwrap_or_else(|| {
+                classes.push(Bitvec::new(n_nodes));
+                classes.len() - 1
+            });
+            classes[c].insert(v);
+            colours[v] = c;
+        }
+        Colouring::new(colours, n_nodes == 0)
+    }
+
+    fn dsatur_colouring(&self) -> Colouring {
+        let n_nodes = self.n_nodes();
+        let mut classes: Vec<Bitvec> = Vec::new();
+        let mut uncoloured = Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes);
+        while let Some(v) = most_saturated(self, &classes, &uncoloured) {
+            let c = free_class(&classes, &self.matrix[v]).unwrap_or_else(|| {
+                classes.push(Bitvec::new(n_nodes));
+                classes.len() - 1
+            });
+            classes[c].insert(v);
+            uncoloured.remove(v);
+        }
+        Colouring::new(colours_from_classes(&classes, n_nodes), n_nodes == 0)
+    }
+
+    fn chromatic_number(&self, time_limit: Option<Duration>) -> Colouring {
+        // the clique search below spends from the same budget
+        let timer = Timer::new(time_limit);
+        let n_nodes = self.n_nodes();
+        let upper = self.dsatur_colouring();
+        let clique = self.maximum_clique(time_limit).clique;
+        if upper.n_colours <= clique.len() {
+            return Colouring::new(upper.colours, true);
+        }
+        // any clique needs distinct colours, fixing them up front breaks the symmetry
+        let classes: Vec<Bitvec> = clique
+            .iter()
+            .map(|&v| Bitvec::from_vector(&[v], n_nodes))
+            .collect();
+        let mut uncoloured = Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes);
+        for &v in &clique {
+            uncoloured.remove(v);
+        }
+        let mut solver = Solver {
+            graph: self,
+            classes,
+            uncoloured,
+            lower_bound: clique.len(),
+            best_n: upper.n_colours,
+            best: None,
+            timer,
+        };
+        solver.expand();
+        let colours = match solver.best {
+            Some(classes) => colours_from_classes(&classes, n_nodes),
+            None => upper.colours,
+        };
+        Colouring::new(colours, !solver.timer.expired())
+    }
+
+    fn clique_cover(&self, time_limit: Option<Duration>) -> Colouring {
+        self.complement().chromatic_number(time_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_proper(graph: &Bitmatrix, colouring: &Colouring) -> bool {
+        (0..graph.n_nodes()).all(|u| {
+            graph
+                .get_neighbours(u)
+                .into_iter()
+                .all(|v| colouring.colours[u] != colouring.colours[v])
+        })
+    }
+
+    #[test]
+    fn test_colourings() {
+        // the Mycielski graph of C5 (Grötzsch graph): triangle-free with chromatic number 4
+        let mut edges = vec![];
+        for i in 0..5 {
+            edges.push((i, (i + 1) % 5));
+            edges.push((i + 5, (i + 1) % 5));
+            edges.push((i + 5, (i + 4) % 5));
+            edges.push((i + 5, 10));
+        }
+        let mut rows = vec![Vec::new(); 11];
+        for &(u, v) in &edges {
+            rows[u].push(v);
+            rows[v].push(u);
+        }
+        let graph = Bitmatrix::new(rows, 11);
+        for colouring in [
+            graph.greedy_colouring(ColourOrder::LargestFirst),
+            graph.greedy_colouring(ColourOrder::SmallestLast),
+            graph.dsatur_colouring(),
+        ] {
+            assert!(is_proper(&graph, &colouring));
+            assert!(colouring.n_colours >= 4);
+        }
+        let exact = graph.chromatic_number(None);
+        assert!(is_proper(&graph, &exact));
+        assert_eq!(exact.n_colours, 4);
+        assert!(exact.optimal);
+        let exact = graph.chromatic_number(Some(Duration::MAX));
+        assert_eq!((exact.n_colours, exact.optimal), (4, true));
+
+        // covering the Grötzsch graph by cliques takes one clique per edge of a maximum
+        // matching plus the unmatched vertex
+        let cover = graph.clique_cover(None);
+        assert_eq!(cover.n_colours, 6);
+        for clique in cover.classes() {
+            for (i, &u) in clique.iter().enumerate() {
+                assert!(clique[i + 1..].iter().all(|&v| graph.matrix[u].contains(v)));
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::search::{CliqueSearch, PivotStrategy};
+use crate::visitor::CliqueVisitor;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+pub struct DegeneracyOrdering {
+    /// vertices in the order they are peeled off, smallest remaining degree first
+    pub order: Vec<usize>,
+    /// position of each vertex inside `order`
+    pub position: Vec<usize>,
+    pub core_numbers: Vec<usize>,
+    pub degeneracy: usize,
+}
+
+/// Core decomposition of the graph (Batagelj–Zaversnik bucket peeling, O(n + m)).
+pub trait Degeneracy {
+    fn degeneracy_ordering(&self) -> DegeneracyOrdering;
+}
+
+impl Degeneracy for Bitmatrix {
+    fn degeneracy_ordering(&self) -> DegeneracyOrdering {
+        let n_nodes = self.n_nodes();
+        let neighbours: Vec<Vec<usize>> = (0..n_nodes).map(|v| self.get_neighbours(v)).collect();
+        let mut degree: Vec<usize> = neighbours.iter().map(|row| row.len()).collect();
+        let max_degree = degree.iter().copied().max().unwrap_or(0);
+        // bin[d] is where the vertices of degree d start inside `order`
+        let mut bin = vec![0; max_degree + 1];
+        for &d in degree.iter() {
+            bin[d] += 1;
+        }
+        let mut start = 0;
+        for slot in bin.iter_mut() {
+            let size = *slot;
+            *slot = start;
+            start += size;
+        }
+        let mut order = vec![0; n_nodes];
+        let mut position = vec![0; n_nodes];
+        for v in 0..n_nodes {
+            position[v] = bin[degree[v]];
+            order[position[v]] = v;
+            bin[degree[v]] += 1;
+        }
+        for d in (1..=max_degree).rev() {
+            bin[d] = bin[d - 1];
+        }
+        if max_degree > 0 {
+            bin[0] = 0;
+        }
+        for i in 0..n_nodes {
+            let v = order[i];
+            for &u in neighbours[v].iter() {
+                if degree[u] > degree[v] {
+                    // move u to the front of its bucket, then shrink its degree
+                    let du = degree[u];
+                    let pu = position[u];
+                    let pw = bin[du];
+                    let w = order[pw];
+                    if u != w {
+                        order.swap(pu, pw);
+                        position[u] = pw;
+                        position[w] = pu;
+                    }
+                    bin[du] += 1;
+                    degree[u] -= 1;
+                }
+            }
+        }
+        let degeneracy = degree.iter().copied().max().unwrap_or(0);
+        DegeneracyOrdering {
+            order,
+            position,
+            core_numbers: degree,
+            degeneracy,
+        }
+    }
+}
+
+/// Maximal clique enumeration following Eppstein, Löffler and Strash.
+/// Vertices are processed in degeneracy order and each outer call only sees the
+/// neighbourhood of its vertex, relabelled into a small local Bitmatrix. Later neighbours
+/// are candidates, earlier ones are excluded, so every clique is found exactly once, from
+/// its first vertex in the ordering. Runs in O(d·n·3^{d/3}) for degeneracy d.
+/// As in the paper, pivots follow Tomita's rule unless another strategy is given.
+pub trait SparseCliques {
+    fn visit_cliques_sparse<V: CliqueVisitor>(&self, visitor: &mut V) -> ControlFlow<()>;
+    /// Same as `visit_cliques_sparse`, choosing pivots inside each neighbourhood with the
+    /// given strategy.
+    fn visit_cliques_sparse_with<V: CliqueVisitor>(
+        &self,
+        pivot: PivotStrategy,
+        visitor: &mut V,
+    ) -> ControlFlow<()>;
+    fn maximal_cliques_sparse(&self) -> Vec<Vec<usize>>;
+}
+
+/// Neighbourhood of a vertex as one outer call of the search sees it. Its later
+/// neighbours (the candidates, at most the degeneracy many) are relabelled into a small
+/// Bitmatrix, while each earlier neighbour only keeps its adjacency into those, which is
+/// all the search ever reads from the excluded vertices. A hub of degree D thus costs
+/// O(d·D) bits rather than O(D²).
+pub(crate) struct Neighbourhood {
+    /// the later neighbours, indexed by their local ids
+    pub later: Vec<usize>,
+    /// induced subgraph on the later neighbours
+    pub graph: Bitmatrix,
+    /// adjacency of each earlier neighbour into the later ones
+    pub earlier: Arc<[Bitvec]>,
+}
+
+impl Neighbourhood {
+    /// Whether the vertex has no neighbours at all, and so is a maximal clique on its own.
+    pub fn is_isolated(&self) -> bool {
+        self.later.is_empty() && self.earlier.is_empty()
+    }
+
+    /// Search for the maximal cliques of the vertex, in local ids without the vertex.
+    pub fn search(&self, pivot: PivotStrategy) -> CliqueSearch {
+        let n_later = self.later.len();
+        let all = Bitvec::from_vector(&(0..n_later).collect::<Vec<usize>>(), n_later);
+        let n_earlier = self.earlier.len();
+        let outside = Bitvec::from_vector(&(0..n_earlier).collect::<Vec<usize>>(), n_earlier);
+        CliqueSearch::from_sets_excluding(
+            &self.graph,
+            all.clone(),
+            all,
+            self.earlier.clone(),
+            outside,
+            pivot,
+        )
+    }
+}
+
+impl Bitmatrix {
+    pub(crate) fn local_neighbourhood(&self, v: usize, position: &[usize]) -> Neighbourhood {
+        let (later, earlier): (Vec<usize>, Vec<usize>) = self
+            .get_neighbours(v)
+            .into_iter()
+            .partition(|&w| position[w] > position[v]);
+        let n_later = later.len();
+        let row_into_later = |w: usize| {
+            let row: Vec<usize> = (0..n_later)
+                .filter(|&j| self.matrix[w].contains(later[j]))
+                .collect();
+            Bitvec::from_vector(&row, n_later)
+        };
+        let rows: Vec<Vec<usize>> = later
+            .iter()
+            .map(|&w| row_into_later(w).elements())
+            .collect();
+        let earlier = earlier.iter().map(|&w| row_into_later(w)).collect();
+        Neighbourhood {
+            graph: Bitmatrix::new(rows, n_later),
+            later,
+            earlier,
+        }
+    }
+}
+
+impl SparseCliques for Bitmatrix {
+    fn visit_cliques_sparse<V: CliqueVisitor>(&self, visitor: &mut V) -> ControlFlow<()> {
+        self.visit_cliques_sparse_with(PivotStrategy::Tomita, visitor)
+    }
+
+    fn visit_cliques_sparse_with<V: CliqueVisitor>(
+        &self,
+        pivot: PivotStrategy,
+        visitor: &mut V,
+    ) -> ControlFlow<()> {
+        let ordering = self.degeneracy_ordering();
+        let mut clique = Vec::new();
+        for &v in ordering.order.iter() {
+            let local = self.local_neighbourhood(v, &ordering.position);
+            if local.is_isolated() {
+                visitor.visit(&[v])?;
+                continue;
+            }
+            let mut search = local.search(pivot);
+            while let Some(found) = search.next_clique(&local.graph) {
+                clique.clear();
+                clique.push(v);
+                clique.extend(found.iter().map(|&i| local.later[i]));
+                visitor.visit(&clique)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn maximal_cliques_sparse(&self) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        let _ = self.visit_cliques_sparse(&mut |clique: &[usize]| {
+            let mut clique = clique.to_vec();
+            clique.sort_unstable();
+            cliques.push(clique);
+            ControlFlow::Continue(())
+        });
+        cliques
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{graph_from_edges, maximal_cliques};
+
+    #[test]
+    fn test_core_numbers() {
+        // a 4-clique with a path hanging off it
+        let graph = graph_from_edges(
+            &[
+                (0, 1),
+                (0, 2),
+                (0, 3),
+                (1, 2),
+                (1, 3),
+                (2, 3),
+                (3, 4),
+                (4, 5),
+            ],
+            6,
+        );
+        let ordering = graph.degeneracy_ordering();
+        assert_eq!(ordering.degeneracy, 3);
+        assert_eq!(ordering.core_numbers, vec![3, 3, 3, 3, 1, 1]);
+        for (i, &v) in ordering.order.iter().enumerate() {
+            assert_eq!(ordering.position[v], i);
+        }
+    }
+    #[test]
+    fn test_sparse_matches_pivot() {
+        let mut edges: Vec<(usize, usize)> = (0..30)
+            .flat_map(|u| (u + 1..30).map(move |v| (u, v)))
+            .filter(|&(u, v)| (u * 7 + v * 13) % 5 < 2)
+            .collect();
+        // a hub next to everything, whose earlier neighbours stay outside its local graph
+        edges.extend((0..30).map(|u| (u, 31)));
+        let graph = graph_from_edges(&edges, 32);
+        let mut expected = maximal_cliques(&graph);
+        expected.sort();
+        for pivot in [
+            PivotStrategy::Degree,
+            PivotStrategy::Tomita,
+            PivotStrategy::Random(3),
+            PivotStrategy::NoPivot,
+        ] {
+            let mut found = Vec::new();
+            let _ = graph.visit_cliques_sparse_with(pivot, &mut |clique: &[usize]| {
+                let mut clique = clique.to_vec();
+                clique.sort_unstable();
+                found.push(clique);
+                ControlFlow::Continue(())
+            });
+            found.sort();
+            assert_eq!(found, expected);
+        }
+        let ordering = graph.degeneracy_ordering();
+        for v in 0..32 {
+            let local = graph.local_neighbourhood(v, &ordering.position);
+            assert!(local.graph.n_nodes() <= ordering.degeneracy);
+        }
+    }
+}
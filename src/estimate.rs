@@ -0,0 +1,284 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::random::Xorshift;
+use crate::search::{CliqueSearch, PivotStrategy};
+
+/// Normal quantile for the two-sided 95% intervals reported by the estimators.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// Sampling estimate of a count: the point `estimate`, a 95% confidence interval
+/// `[lower, upper]` and the number of samples drawn. When the count comes out exact
+/// without sampling, it reports zero samples and a degenerate interval.
+#[derive(Clone, Copy, Debug)]
+pub struct Estimate {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub samples: u64,
+}
+
+impl Estimate {
+    fn exact(count: f64) -> Self {
+        Estimate {
+            estimate: count,
+            lower: count,
+            upper: count,
+            samples: 0,
+        }
+    }
+}
+
+/// Binomial coefficient as a float, which stays finite far beyond `u64`.
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Uniform `size`-subset of `0..n` (Floyd's algorithm).
+fn random_subset(rng: &mut Xorshift, n: usize, size: usize) -> Vec<usize> {
+    let mut subset: Vec<usize> = Vec::with_capacity(size);
+    for j in n - size..n {
+        let t = rng.below(j + 1);
+        subset.push(if subset.contains(&t) { j } else { t });
+    }
+    subset
+}
+
+/// Turán shadow of the k-cliques (Jain and Seshadhri, 2017): pairs (S, l) such that
+/// every k-clique is an l-clique of exactly one S plus the vertices fixed on the way
+/// down to it. A pair is split along the degeneracy order of S into the later
+/// neighbourhood of each vertex with l - 1, until S has more edges than Turán's bound
+/// for l-clique free graphs, so that a random l-subset of it is a clique often enough
+/// to sample. Pairs with l = 2 are counted on the spot.
+struct TuranShadow {
+    counted: f64,
+    leaves: Vec<(Vec<usize>, usize)>,
+}
+
+impl TuranShadow {
+    fn new(graph: &Bitmatrix, k: usize) -> Self {
+        let mut shadow = TuranShadow {
+            counted: 0.0,
+            leaves: Vec::new(),
+        };
+        let mut pending = Vec::new();
+        shadow.shade(graph, (0..graph.n_nodes()).collect(), k, &mut pending);
+        while let Some((vertices, l)) = pending.pop() {
+            let local = graph.induced_subgraph(&vertices);
+            shadow.shade(&local, vertices, l, &mut pending);
+        }
+        shadow
+    }
+
+    /// Counts, keeps or splits the pair (`vertices`, `l`), where `local` is the subgraph
+    /// induced by `vertices`.
+    fn shade(
+        &mut self,
+        local: &Bitmatrix,
+        vertices: Vec<usize>,
+        l: usize,
+        pending: &mut Vec<(Vec<usize>, usize)>,
+    ) {
+        let size = vertices.len();
+        let edges = local
+            .matrix
+            .iter()
+            .map(|row| row.n_elements())
+            .sum::<usize>()
+            / 2;
+        if l == 2 {
+            self.counted += edges as f64;
+            return;
+        }
+        if edges as f64 > (1.0 - 1.0 / (l - 1) as f64) * (size * size) as f64 / 2.0 {
+            self.leaves.push((vertices, l));
+            return;
+        }
+        let position = local.degeneracy_ordering().position;
+        for v in 0..size {
+            let later: Vec<usize> = local
+                .get_neighbours(v)
+                .into_iter()
+                .filter(|&w| position[w] > position[v])
+                .map(|w| vertices[w])
+                .collect();
+            if later.len() >= l - 1 {
+                pending.push((later, l - 1));
+            }
+        }
+    }
+}
+
+/// Sampling estimators for clique counts on graphs too large to enumerate.
+pub trait CliqueEstimates {
+    /// Estimates the number of k-cliques from their Turán shadow. Whatever the shadow
+    /// counts exactly is added as is; a sample picks one of its dense pairs (S, l) with
+    /// probability proportional to C(|S|, l) and a uniform l-subset of S, and hits when
+    /// the subset is a clique. The rest of the count is the total number of such subsets
+    /// times the hit rate, which Turán's theorem keeps away from zero even in dense
+    /// graphs with few k-cliques. The interval is Wilson's score interval for the hit
+    /// rate, scaled the same way. `samples` must be positive.
+    fn estimate_k_cliques(&self, k: usize, samples: u64, seed: u64) -> Estimate;
+    /// Estimates the number of maximal cliques with Knuth's random path estimator: each
+    /// sample walks from the root of the pivoting search tree to a leaf, choosing
+    /// children uniformly at random (Tomita pivots keep the tree narrow), and is worth
+    /// the product of the branching factors along the way if the leaf is a maximal
+    /// clique (nothing otherwise). The interval is the normal one around the sample mean,
+    /// which needs at least two samples.
+    fn estimate_maximal_cliques(&self, samples: u64, seed: u64) -> Estimate;
+}
+
+impl CliqueEstimates for Bitmatrix {
+    fn estimate_k_cliques(&self, k: usize, samples: u64, seed: u64) -> Estimate {
+        assert!(samples > 0, "There must be at least one sample");
+        let n_nodes = self.n_nodes();
+        if k <= 1 {
+            return Estimate::exact(if k == 1 { n_nodes as f64 } else { 0.0 });
+        }
+        let shadow = TuranShadow::new(self, k);
+        if shadow.leaves.is_empty() {
+            return Estimate::exact(shadow.counted);
+        }
+        // running totals of the subset counts, to pick leaves by binary search
+        let mut cumulative = Vec::with_capacity(shadow.leaves.len());
+        let mut total = 0.0;
+        for (vertices, l) in &shadow.leaves {
+            total += binomial(vertices.len(), *l);
+            cumulative.push(total);
+        }
+        let mut rng = Xorshift::new(seed);
+        let mut hits = 0u64;
+        for _ in 0..samples {
+            let target = rng.next_f64() * total;
+            let leaf = cumulative
+                .partition_point(|&c| c <= target)
+                .min(shadow.leaves.len() - 1);
+            let (vertices, l) = &shadow.leaves[leaf];
+            let subset: Vec<usize> = random_subset(&mut rng, vertices.len(), *l)
+                .into_iter()
+                .map(|i| vertices[i])
+                .collect();
+            let is_clique = subset
+                .iter()
+                .enumerate()
+                .all(|(i, &a)| subset[i + 1..].iter().all(|&b| self.matrix[a].contains(b)));
+            if is_clique {
+                hits += 1;
+            }
+        }
+        let n = samples as f64;
+        let p = hits as f64 / n;
+        let z2 = Z_95 * Z_95;
+        let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half = Z_95 / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+        // the score interval reaches 0 and 1 exactly at the extremes, keep rounding out
+        let lower = if hits == 0 { 0.0 } else { centre - half };
+        let upper = if hits == samples { 1.0 } else { centre + half };
+        Estimate {
+            estimate: shadow.counted + total * p,
+            lower: shadow.counted + total * lower,
+            upper: shadow.counted + total * upper,
+            samples,
+        }
+    }
+
+    fn estimate_maximal_cliques(&self, samples: u64, seed: u64) -> Estimate {
+        assert!(samples >= 2, "There must be at least two samples");
+        let n_nodes = self.n_nodes();
+        if n_nodes == 0 {
+            return Estimate::exact(0.0);
+        }
+        let full = Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes);
+        let mut rng = Xorshift::new(seed);
+        let (mut sum, mut sum_squares) = (0.0, 0.0);
+        for _ in 0..samples {
+            let mut weight = 1.0;
+            let (mut subg, mut cand) = (full.clone(), full.clone());
+            let value = loop {
+                let branches = CliqueSearch::from_sets(self, subg, cand, PivotStrategy::Tomita)
+                    .into_branches(self);
+                if branches.is_empty() {
+                    break 0.0;
+                }
+                weight *= branches.len() as f64;
+                let chosen = rng.below(branches.len());
+                let branch = branches.into_iter().nth(chosen).unwrap();
+                if branch.subg.is_empty() {
+                    break weight;
+                }
+                subg = branch.subg;
+                cand = branch.cand;
+            };
+            sum += value;
+            sum_squares += value * value;
+        }
+        let n = samples as f64;
+        let mean = sum / n;
+        let variance = ((sum_squares - n * mean * mean) / (n - 1.0)).max(0.0);
+        let half = Z_95 * (variance / n).sqrt();
+        Estimate {
+            estimate: mean,
+            lower: (mean - half).max(0.0),
+            upper: mean + half,
+            samples,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kclique::KCliques;
+    use crate::testutil::{graph_where, maximal_cliques, pseudo_random_graph};
+
+    #[test]
+    fn test_estimates() {
+        let n_nodes = 40;
+        let graph = pseudo_random_graph(n_nodes, 11, 5, 3);
+        // edges are always counted, and this graph has no pair dense enough to sample
+        // triangles from
+        for k in 2..4 {
+            let estimate = graph.estimate_k_cliques(k, 20_000, k as u64);
+            assert_eq!(estimate.samples, 0);
+            assert_eq!(estimate.estimate, graph.count_k_cliques(k) as f64);
+        }
+        for k in 4..7 {
+            let exact = graph.count_k_cliques(k) as f64;
+            let estimate = graph.estimate_k_cliques(k, 20_000, k as u64);
+            assert_eq!(estimate.samples, 20_000);
+            assert!(
+                estimate.lower <= exact && exact <= estimate.upper,
+                "k = {}",
+                k
+            );
+            assert!((estimate.estimate - exact).abs() <= 0.1 * exact);
+        }
+        let exact = maximal_cliques(&graph).len() as f64;
+        let estimate = graph.estimate_maximal_cliques(5_000, 7);
+        assert!(estimate.lower <= exact && exact <= estimate.upper);
+    }
+    #[test]
+    fn test_estimate_dense() {
+        // complete 4-partite graph on parts of 4, 6, 8 and 10 vertices: dense, yet free of
+        // 5-cliques until a path of two edges goes inside the last part
+        let part = |v: usize| [4, 10, 18].iter().filter(|&&start| v >= start).count();
+        let turan = graph_where(28, |u, v| part(u) != part(v));
+        let estimate = turan.estimate_k_cliques(5, 1_000, 1);
+        assert_eq!(
+            (estimate.estimate, estimate.upper, estimate.samples),
+            (0.0, 0.0, 0)
+        );
+        let graph = graph_where(28, |u, v| {
+            part(u) != part(v) || matches!((u.min(v), u.max(v)), (20, 21) | (21, 22))
+        });
+        let exact = graph.count_k_cliques(5) as f64;
+        let estimate = graph.estimate_k_cliques(5, 20_000, 5);
+        assert_eq!(estimate.samples, 20_000);
+        assert!(estimate.lower <= exact && exact <= estimate.upper);
+        assert!((estimate.estimate - exact).abs() <= 0.1 * exact);
+    }
+}
@@ -1,31 +1,309 @@
-use crate::bitmatrix::Bitmatrix;
-use crate::bitvec::Bitvec;
-use crate::bitmatrix::BoundaryMatrix;
-use crate::linalg::rank;
-
-pub fn betti_numbers(adjacency_matrix:Bitmatrix) -> Vec<usize>{
-  //start variables
-  let mut betti_numbers = Vec::new();
-  let mut n_cliques_k = adjacency_matrix.matrix.len();
-  //you have to hold 2 values
-  let mut cliques_k:Vec<Bitvec> = Vec::new();
-  for node in 0..n_cliques_k{
-    cliques_k.push(Bitvec::from_vector(&[node], n_cliques_k));
-  }
-  let mut rk = 0;
-  //loop for k+1
-  loop {
-    // get k1 info
-    let (cliques_k1,mut delta) = adjacency_matrix.boundary_matrix(&cliques_k);
-    let n_cliques_k1 = cliques_k1.len();
-    if n_cliques_k1==0{break}
-    let rk1 = rank(&mut delta,n_cliques_k);
-    let bk = n_cliques_k - (rk + rk1 );
-    betti_numbers.push(bk);
-    //update k+1 -> k
-    cliques_k = cliques_k1;
-    n_cliques_k = n_cliques_k1;
-    rk = rk1;
-  };
-  betti_numbers
+use crate::bitmatrix::{Bitmatrix, NextCliques};
+use crate::levels::{CliqueLevel, LEVEL_CHUNK};
+use crate::limits::{Budget, Limits, StopReason};
+use crate::linalg::{smith_normal_form, CoefficientOverflow, Field, FieldRank, SparseRank};
+
+/// Faces of a clique as their indices in the level below, each with the position of
+/// the vertex left out. Leaving out vertices from the last to the first gives the faces
+/// in lexicographic order.
+fn faces_of<'a>(
+    clique: &'a [usize],
+    faces: &'a CliqueLevel,
+    face: &'a mut Vec<usize>,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    (0..clique.len()).rev().map(move |skip| {
+        face.clear();
+        face.extend(
+            clique
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != skip)
+                .map(|(_, &v)| v),
+        );
+        let index = faces
+            .position(face)
+            .expect("every face of a clique is a clique");
+        (index, skip)
+    })
+}
+
+/// Boundary of a clique as the sorted indices of its faces in the level below.
+fn boundary(clique: &[usize], faces: &CliqueLevel, face: &mut Vec<usize>) -> Vec<usize> {
+    faces_of(clique, faces, face)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Oriented boundary of a clique, its vertices taken in increasing order: the face
+/// leaving out the i-th vertex has sign (-1)^i. Sorted by face index.
+pub(crate) fn oriented_boundary(
+    clique: &[usize],
+    faces: &CliqueLevel,
+    face: &mut Vec<usize>,
+) -> Vec<(usize, i64)> {
+    faces_of(clique, faces, face)
+        .map(|(index, skip)| (index, if skip % 2 == 0 { 1 } else { -1 }))
+        .collect()
+}
+
+/// Rank of a boundary map, built up one clique at a time.
+trait BoundaryRank {
+    fn add_boundary(&mut self, clique: &[usize], faces: &CliqueLevel, face: &mut Vec<usize>);
+
+    fn rank(&self) -> usize;
+}
+
+impl BoundaryRank for SparseRank {
+    fn add_boundary(&mut self, clique: &[usize], faces: &CliqueLevel, face: &mut Vec<usize>) {
+        self.add_column(boundary(clique, faces, face));
+    }
+
+    fn rank(&self) -> usize {
+        SparseRank::rank(self)
+    }
+}
+
+impl<F: Field> BoundaryRank for FieldRank<F> {
+    fn add_boundary(&mut self, clique: &[usize], faces: &CliqueLevel, face: &mut Vec<usize>) {
+        let field = self.field();
+        let column = oriented_boundary(clique, faces, face)
+            .into_iter()
+            .map(|(index, sign)| (index, field.embed(sign)))
+            .collect();
+        self.add_column(column);
+    }
+
+    fn rank(&self) -> usize {
+        FieldRank::rank(self)
+    }
+}
+
+/// Betti numbers (over Z/2) of the clique complex, dimension by dimension. Stops after
+/// `limits.max_dimension` or when the time budget runs out or `interrupt` returns true,
+/// returning the Betti numbers completed so far and why it stopped.
+pub fn betti_numbers_limited(
+    adjacency_matrix: &Bitmatrix,
+    limits: &Limits,
+    interrupt: &mut dyn FnMut() -> bool,
+) -> (Vec<usize>, Option<StopReason>) {
+    betti_numbers_with(adjacency_matrix, limits, interrupt, SparseRank::new)
+}
+
+/// Betti numbers of the clique complex with coefficients in `field`, stopping like
+/// [`betti_numbers_limited`]. Over a field of characteristic two the signs of the
+/// boundary do not matter and the Z/2 elimination is used instead.
+pub fn betti_numbers_over<F: Field>(
+    adjacency_matrix: &Bitmatrix,
+    field: F,
+    limits: &Limits,
+    interrupt: &mut dyn FnMut() -> bool,
+) -> (Vec<usize>, Option<StopReason>) {
+    if field.characteristic() == 2 {
+        return betti_numbers_limited(adjacency_matrix, limits, interrupt);
+    }
+    betti_numbers_with(adjacency_matrix, limits, interrupt, || {
+        FieldRank::new(field)
+    })
+}
+
+/// Each level is kept as flat sorted ids; the level above is streamed in chunks whose
+/// boundaries are reduced as they arrive, so no boundary matrix is ever stored.
+fn betti_numbers_with<R: BoundaryRank>(
+    adjacency_matrix: &Bitmatrix,
+    limits: &Limits,
+    interrupt: &mut dyn FnMut() -> bool,
+    new_rank: impl Fn() -> R,
+) -> (Vec<usize>, Option<StopReason>) {
+    let mut budget = Budget::new(limits, interrupt);
+    let mut betti_numbers = Vec::new();
+    let mut cliques_k = CliqueLevel::singletons(adjacency_matrix.n_nodes());
+    // rank of the boundary map into the current level
+    let mut rk = 0;
+    let mut face = Vec::new();
+    'levels: while !cliques_k.is_empty() {
+        let mut cliques_k1 = CliqueLevel::new(cliques_k.size() + 1);
+        let mut rank_k1 = new_rank();
+        for mut chunk in adjacency_matrix.next_cliques_chunked(&cliques_k, LEVEL_CHUNK) {
+            if budget.check().is_break() {
+                break 'levels;
+            }
+            for clique in chunk.iter() {
+                rank_k1.add_boundary(clique, &cliques_k, &mut face);
+            }
+            cliques_k1.append(&mut chunk);
+        }
+        let rk1 = rank_k1.rank();
+        betti_numbers.push(cliques_k.len() - (rk + rk1));
+        if !cliques_k1.is_empty() && limits.max_dimension == Some(betti_numbers.len() - 1) {
+            let _ = budget.stop(StopReason::MaxDimension);
+            break;
+        }
+        cliques_k = cliques_k1;
+        rk = rk1;
+    }
+    (betti_numbers, budget.stopped)
+}
+
+/// Free rank and torsion coefficients of an integral homology group.
+pub type HomologyGroup = (usize, Vec<u64>);
+
+/// Integral homology of the clique complex: for each dimension k, the rank of H_k(K; Z)
+/// and its torsion coefficients, each dividing the next. Stops like
+/// [`betti_numbers_limited`]; fails only if the elimination outgrows its integers.
+///
+/// The torsion of H_k sits in the cokernel of the boundary map from dimension k + 1, so
+/// each level's signed boundary matrix is collected and brought to Smith normal form
+/// once the level above is complete.
+pub fn integral_homology_limited(
+    adjacency_matrix: &Bitmatrix,
+    limits: &Limits,
+    interrupt: &mut dyn FnMut() -> bool,
+) -> Result<(Vec<HomologyGroup>, Option<StopReason>), CoefficientOverflow> {
+    let mut budget = Budget::new(limits, interrupt);
+    let mut homology = Vec::new();
+    let mut cliques_k = CliqueLevel::singletons(adjacency_matrix.n_nodes());
+    // rank of the boundary map into the current level
+    let mut rk = 0;
+    let mut face = Vec::new();
+    'levels: while !cliques_k.is_empty() {
+        let mut cliques_k1 = CliqueLevel::new(cliques_k.size() + 1);
+        let mut columns = Vec::new();
+        for mut chunk in adjacency_matrix.next_cliques_chunked(&cliques_k, LEVEL_CHUNK) {
+            if budget.check().is_break() {
+                break 'levels;
+            }
+            for clique in chunk.iter() {
+                columns.push(oriented_boundary(clique, &cliques_k, &mut face));
+            }
+            cliques_k1.append(&mut chunk);
+        }
+        let form = smith_normal_form(cliques_k.len(), columns, &mut || budget.check().is_break())?;
+        let Some(form) = form else {
+            break;
+        };
+        homology.push((cliques_k.len() - (rk + form.rank), form.torsion));
+        if !cliques_k1.is_empty() && limits.max_dimension == Some(homology.len() - 1) {
+            let _ = budget.stop(StopReason::MaxDimension);
+            break;
+        }
+        cliques_k = cliques_k1;
+        rk = form.rank;
+    }
+    Ok((homology, budget.stopped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::PrimeField;
+    use crate::testutil::graph_from_edges;
+
+    #[test]
+    fn test_betti_numbers() {
+        // octahedron: the hollow sphere, with a 2-cycle in its top dimension
+        let rows: Vec<Vec<usize>> = (0..6)
+            .map(|u| (0..6).filter(|&v| v != u && v != u ^ 1).collect())
+            .collect();
+        let graph = Bitmatrix::new(rows, 6);
+        let (betti, stopped) = betti_numbers_limited(&graph, &Limits::default(), &mut || false);
+        assert_eq!((betti, stopped), (vec![1, 0, 1], None));
+        let (homology, _) =
+            integral_homology_limited(&graph, &Limits::default(), &mut || false).unwrap();
+        assert_eq!(homology, vec![(1, vec![]), (0, vec![]), (1, vec![])]);
+        let limits = Limits {
+            max_dimension: Some(1),
+            ..Limits::default()
+        };
+        let (betti, stopped) = betti_numbers_limited(&graph, &limits, &mut || false);
+        assert_eq!(betti, vec![1, 0]);
+        assert_eq!(stopped, Some(StopReason::MaxDimension));
+        let (betti, stopped) = betti_numbers_limited(&graph, &Limits::default(), &mut || true);
+        assert!(betti.is_empty());
+        assert_eq!(stopped, Some(StopReason::Interrupted));
+    }
+
+    #[test]
+    fn test_top_dimension() {
+        // the last level has no cofaces, but its cycles still count
+        let betti = |edges: &[(usize, usize)], n_nodes| {
+            betti_numbers_limited(
+                &graph_from_edges(edges, n_nodes),
+                &Limits::default(),
+                &mut || false,
+            )
+            .0
+        };
+        assert_eq!(betti(&[], 2), vec![2]);
+        assert_eq!(betti(&[(0, 1)], 2), vec![1, 0]);
+        assert_eq!(
+            betti(&[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)], 5),
+            vec![1, 1]
+        );
+        // a filled triangle with a square hanging off it
+        assert_eq!(
+            betti(&[(0, 1), (1, 2), (0, 2), (2, 3), (3, 4), (4, 5), (5, 2)], 6),
+            vec![1, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_torsion() {
+        // barycentric subdivision of the six-vertex projective plane, as the comparability
+        // graph of its faces: H_1 is Z/2, seen over Z/2 but invisible over Z/3
+        let triangles = [
+            [0, 1, 2],
+            [0, 2, 3],
+            [0, 3, 4],
+            [0, 4, 5],
+            [0, 1, 5],
+            [1, 2, 4],
+            [2, 3, 5],
+            [1, 3, 4],
+            [2, 4, 5],
+            [1, 3, 5],
+        ];
+        let mut simplices: Vec<Vec<usize>> = Vec::new();
+        for triangle in triangles {
+            for mask in 1..8usize {
+                let simplex: Vec<usize> = (0..3)
+                    .filter(|i| mask >> i & 1 == 1)
+                    .map(|i| triangle[i])
+                    .collect();
+                if !simplices.contains(&simplex) {
+                    simplices.push(simplex);
+                }
+            }
+        }
+        let n = simplices.len();
+        let contains = |a: &Vec<usize>, b: &Vec<usize>| b.iter().all(|v| a.contains(v));
+        let rows: Vec<Vec<usize>> = (0..n)
+            .map(|u| {
+                (0..n)
+                    .filter(|&v| {
+                        v != u
+                            && (contains(&simplices[u], &simplices[v])
+                                || contains(&simplices[v], &simplices[u]))
+                    })
+                    .collect()
+            })
+            .collect();
+        let graph = Bitmatrix::new(rows, n);
+        let over = |p| {
+            let field = PrimeField::new(p).unwrap();
+            betti_numbers_over(&graph, field, &Limits::default(), &mut || false).0
+        };
+        assert_eq!(over(2), vec![1, 1, 1]);
+        assert_eq!(over(3), vec![1, 0, 0]);
+        assert_eq!(over(7), vec![1, 0, 0]);
+        assert!(PrimeField::new(9).is_none());
+        assert!(PrimeField::new(4_294_967_291).is_some());
+        assert!(PrimeField::new(4_294_967_295).is_none());
+        assert!(PrimeField::new(4_294_967_311).is_none());
+        assert!(PrimeField::new(18_446_744_073_709_551_557).is_none());
+        assert!(PrimeField::new(u64::MAX).is_none());
+        let (homology, stopped) =
+            integral_homology_limited(&graph, &Limits::default(), &mut || false).unwrap();
+        assert_eq!(homology, vec![(1, vec![]), (0, vec![2]), (0, vec![])]);
+        assert_eq!(stopped, None);
+    }
 }
@@ -0,0 +1,433 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::limits::Timer;
+use crate::visitor::CliqueVisitor;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+/// Largest independent set found by the solver, with the same time-limit semantics as
+/// `BestClique`.
+pub struct BestIndependentSet {
+    pub set: Vec<usize>,
+    pub optimal: bool,
+}
+
+/// Takes `v` and its neighbours out of `pool`, returning the ones that were in it so they
+/// can be put back.
+fn take_neighbourhood(pool: &mut Bitvec, v: usize, neighbours: &[usize]) -> Vec<usize> {
+    let mut taken = Vec::new();
+    for &u in std::iter::once(&v).chain(neighbours) {
+        if pool.contains(u) {
+            pool.remove(u);
+            taken.push(u);
+        }
+    }
+    taken
+}
+
+/// A level of the independent set enumeration. Only what changed on the way down is
+/// kept, so a level costs about the degree of its vertex rather than two bitsets.
+struct Level {
+    branches: Vec<usize>,
+    // how many of `branches` were taken, each moved from P to X once explored
+    taken: usize,
+    // vertices of P and X that went out with the vertex added on the way down
+    left_cand: Vec<usize>,
+    left_excl: Vec<usize>,
+}
+
+/// Bron–Kerbosch on the complement, without building it: `cand` and `excl` (P and X)
+/// shrink to the non-neighbours of each vertex added to the set. The pivot is the vertex
+/// of P ∪ X independent of the most candidates, and only the candidates adjacent to it
+/// (or the pivot itself) are branched on. To find it without scanning P ∪ X, every
+/// vertex keeps its score |P ∩ N(u)| + [u ∈ P], and those of P ∪ X are chained in one
+/// doubly linked list per score.
+/// The search is iterative like `CliqueSearch`: in a sparse graph the independent sets,
+/// and so the levels of the search, are about as many as the vertices.
+struct SetSearch {
+    neighbours: Vec<Vec<usize>>,
+    cand: Bitvec,
+    excl: Bitvec,
+    score: Vec<usize>,
+    head: Vec<Option<usize>>,
+    next: Vec<Option<usize>>,
+    prev: Vec<Option<usize>>,
+}
+
+impl SetSearch {
+    fn new(graph: &Bitmatrix) -> Self {
+        let n_nodes = graph.n_nodes();
+        let neighbours: Vec<Vec<usize>> = (0..n_nodes).map(|v| graph.get_neighbours(v)).collect();
+        let mut search = SetSearch {
+            score: neighbours.iter().map(|row| row.len() + 1).collect(),
+            neighbours,
+            cand: Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes),
+            excl: Bitvec::new(n_nodes),
+            head: vec![None; n_nodes + 1],
+            next: vec![None; n_nodes],
+            prev: vec![None; n_nodes],
+        };
+        for u in 0..n_nodes {
+            search.link(u);
+        }
+        search
+    }
+
+    fn link(&mut self, u: usize) {
+        let score = self.score[u];
+        self.prev[u] = None;
+        self.next[u] = self.head[score];
+        if let Some(w) = self.head[score] {
+            self.prev[w] = Some(u);
+        }
+        self.head[score] = Some(u);
+    }
+
+    fn unlink(&mut self, u: usize) {
+        match self.prev[u] {
+            Some(w) => self.next[w] = self.next[u],
+            None => self.head[self.score[u]] = self.next[u],
+        }
+        if let Some(w) = self.next[u] {
+            self.prev[w] = self.prev[u];
+        }
+    }
+
+    /// Counts one more or one less candidate next to each neighbour of `w`.
+    fn rescore_neighbours(&mut self, w: usize, gained: bool) {
+        for i in 0..self.neighbours[w].len() {
+            let u = self.neighbours[w][i];
+            let listed = self.cand.contains(u) || self.excl.contains(u);
+            if listed {
+                self.unlink(u);
+            }
+            if gained {
+                self.score[u] += 1;
+            } else {
+                self.score[u] -= 1;
+            }
+            if listed {
+                self.link(u);
+            }
+        }
+    }
+
+    fn take_cand(&mut self, w: usize) {
+        self.unlink(w);
+        self.score[w] -= 1;
+        self.cand.remove(w);
+        self.rescore_neighbours(w, false);
+    }
+
+    fn put_cand(&mut self, w: usize) {
+        self.score[w] += 1;
+        self.cand.insert(w);
+        self.link(w);
+        self.rescore_neighbours(w, true);
+    }
+
+    fn take_excl(&mut self, w: usize) {
+        self.unlink(w);
+        self.excl.remove(w);
+    }
+
+    fn put_excl(&mut self, w: usize) {
+        self.excl.insert(w);
+        self.link(w);
+    }
+
+    /// Adds `v` to the set: it and its neighbours leave P and X. Returns what left each.
+    fn descend(&mut self, v: usize) -> (Vec<usize>, Vec<usize>) {
+        let mut left_cand = vec![v];
+        let mut left_excl = Vec::new();
+        self.take_cand(v);
+        for i in 0..self.neighbours[v].len() {
+            let u = self.neighbours[v][i];
+            if self.cand.contains(u) {
+                self.take_cand(u);
+                left_cand.push(u);
+            } else if self.excl.contains(u) {
+                self.take_excl(u);
+                left_excl.push(u);
+            }
+        }
+        (left_cand, left_excl)
+    }
+
+    /// Undoes a level: its explored branches go back from X to P, and what left P and X
+    /// on the way down comes back.
+    fn ascend(&mut self, level: &Level) {
+        for &v in &level.branches[..level.taken] {
+            self.take_excl(v);
+            self.put_cand(v);
+        }
+        for &u in &level.left_cand {
+            self.put_cand(u);
+        }
+        for &u in &level.left_excl {
+            self.put_excl(u);
+        }
+    }
+
+    /// Candidates to branch on at the current level.
+    fn branches(&self) -> Vec<usize> {
+        let Some(pivot) = self.head.iter().find_map(|&u| u) else {
+            return Vec::new();
+        };
+        let mut branches: Vec<usize> = self.neighbours[pivot]
+            .iter()
+            .copied()
+            .filter(|&w| self.cand.contains(w))
+            .collect();
+        if self.cand.contains(pivot) {
+            branches.push(pivot);
+        }
+        branches
+    }
+
+    fn visit<V: CliqueVisitor>(&mut self, visitor: &mut V) -> ControlFlow<()> {
+        let mut set = Vec::new();
+        let mut stack = vec![Level {
+            branches: self.branches(),
+            taken: 0,
+            left_cand: Vec::new(),
+            left_excl: Vec::new(),
+        }];
+        while let Some(level) = stack.last_mut() {
+            match level.branches.get(level.taken) {
+                Some(&v) => {
+                    level.taken += 1;
+                    set.push(v);
+                    let (left_cand, left_excl) = self.descend(v);
+                    let branches = if self.cand.is_empty() {
+                        if self.excl.is_empty() {
+                            visitor.visit(&set)?;
+                        }
+                        Vec::new()
+                    } else {
+                        self.branches()
+                    };
+                    stack.push(Level {
+                        branches,
+                        taken: 0,
+                        left_cand,
+                        left_excl,
+                    });
+                }
+                None => {
+                    let level = stack.pop().unwrap();
+                    self.ascend(&level);
+                    if let Some(v) = set.pop() {
+                        self.take_cand(v);
+                        self.put_excl(v);
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Greedy partition of `pool` into cliques, the counterpart of `colour_sort` for
+/// independent sets: an independent set takes at most one vertex per clique, so the
+/// number of cliques up to and including a vertex's bounds any independent set among
+/// it and the vertices before it.
+fn clique_sort(graph: &Bitmatrix, pool: &Bitvec, min_class: usize) -> Vec<(usize, usize)> {
+    let mut sorted = Vec::new();
+    let mut uncovered = pool.clone();
+    let mut class = 0;
+    while !uncovered.is_empty() {
+        class += 1;
+        let mut available = uncovered.clone();
+        while let Some(v) = available.pop() {
+            available.intersection_with(&graph.matrix[v]);
+            uncovered.remove(v);
+            if class >= min_class {
+                sorted.push((v, class));
+            }
+        }
+    }
+    sorted
+}
+
+struct Solver<'a> {
+    graph: &'a Bitmatrix,
+    neighbours: Vec<Vec<usize>>,
+    set: Vec<usize>,
+    best_size: usize,
+    best: Option<Vec<usize>>,
+    timer: Timer,
+}
+
+/// A level of the branch and bound: the vertices still to try, in the order `clique_sort`
+/// left them, and the ones that left the pool with the vertex added on the way down.
+struct SolverLevel {
+    sorted: Vec<(usize, usize)>,
+    left: Vec<usize>,
+}
+
+impl Solver<'_> {
+    fn sorted(&self, pool: &Bitvec) -> Vec<(usize, usize)> {
+        let min_class = (self.best_size + 1).saturating_sub(self.set.len());
+        clique_sort(self.graph, pool, min_class)
+    }
+
+    /// Runs the search over `pool`, iteratively for the same reason as `SetSearch`.
+    fn run(&mut self, mut pool: Bitvec) {
+        let mut stack = vec![SolverLevel {
+            sorted: self.sorted(&pool),
+            left: Vec::new(),
+        }];
+        while let Some(level) = stack.last_mut() {
+            match level.sorted.pop() {
+                Some((v, class))
+                    if self.set.len() + class > self.best_size && !self.timer.tick() =>
+                {
+                    self.set.push(v);
+                    let left = take_neighbourhood(&mut pool, v, &self.neighbours[v]);
+                    if pool.is_empty() && self.set.len() > self.best_size {
+                        self.best_size = self.set.len();
+                        self.best = Some(self.set.clone());
+                    }
+                    stack.push(SolverLevel {
+                        sorted: self.sorted(&pool),
+                        left,
+                    });
+                }
+                _ => {
+                    let level = stack.pop().unwrap();
+                    for &u in &level.left {
+                        pool.insert(u);
+                    }
+                    if let Some(v) = self.set.pop() {
+                        pool.remove(v);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Independent sets, enumerated and optimised directly on the adjacency bitsets rather
+/// than on a stored complement graph.
+pub trait IndependentSets {
+    /// Hands every maximal independent set to `visitor` until it asks to stop.
+    fn visit_independent_sets<V: CliqueVisitor>(&self, visitor: &mut V) -> ControlFlow<()>;
+    /// Maximum independent set by branch and bound with greedy clique partition bounds,
+    /// seeded with a minimum-degree greedy set.
+    fn maximum_independent_set(&self, time_limit: Option<Duration>) -> BestIndependentSet;
+}
+
+impl IndependentSets for Bitmatrix {
+    fn visit_independent_sets<V: CliqueVisitor>(&self, visitor: &mut V) -> ControlFlow<()> {
+        let n_nodes = self.n_nodes();
+        if n_nodes == 0 {
+            return ControlFlow::Continue(());
+        }
+        SetSearch::new(self).visit(visitor)
+    }
+
+    fn maximum_independent_set(&self, time_limit: Option<Duration>) -> BestIndependentSet {
+        let n_nodes = self.n_nodes();
+        let pool = Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes);
+        let neighbours: Vec<Vec<usize>> = (0..n_nodes).map(|v| self.get_neighbours(v)).collect();
+        // taking low degree vertices first rules out the fewest others
+        let mut order: Vec<usize> = (0..n_nodes).collect();
+        order.sort_by_key(|&v| neighbours[v].len());
+        let mut best: Vec<usize> = Vec::new();
+        let mut free = pool.clone();
+        for v in order {
+            if free.contains(v) {
+                best.push(v);
+                take_neighbourhood(&mut free, v, &neighbours[v]);
+            }
+        }
+        let mut solver = Solver {
+            graph: self,
+            neighbours,
+            set: Vec::new(),
+            best_size: best.len(),
+            best: None,
+            timer: Timer::new(time_limit),
+        };
+        solver.run(pool);
+        if let Some(found) = solver.best {
+            best = found;
+        }
+        best.sort_unstable();
+        BestIndependentSet {
+            set: best,
+            optimal: !solver.timer.expired(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{graph_from_edges, maximal_cliques, pseudo_random_graph};
+
+    #[test]
+    fn test_independent_sets() {
+        let n_nodes = 30;
+        let graph = pseudo_random_graph(n_nodes, 13, 5, 3);
+        let mut expected = maximal_cliques(&graph.complement());
+        expected.sort();
+        let mut found = Vec::new();
+        let _ = graph.visit_independent_sets(&mut |set: &[usize]| {
+            let mut set = set.to_vec();
+            set.sort_unstable();
+            found.push(set);
+            ControlFlow::Continue(())
+        });
+        found.sort();
+        assert_eq!(found, expected);
+
+        let best = graph.maximum_independent_set(None);
+        assert!(best.optimal);
+        assert_eq!(
+            best.set.len(),
+            expected.iter().map(|s| s.len()).max().unwrap()
+        );
+        for (i, &u) in best.set.iter().enumerate() {
+            assert!(best.set[i + 1..]
+                .iter()
+                .all(|&v| !graph.matrix[u].contains(v)));
+        }
+        let unlimited = graph.maximum_independent_set(Some(Duration::MAX));
+        assert!(unlimited.optimal);
+        assert_eq!(unlimited.set.len(), best.set.len());
+
+        // the greedy seed is three short of the maximum here, so the search has to improve it
+        let graph = pseudo_random_graph(16, 2, 7, 3);
+        let expected = maximal_cliques(&graph.complement())
+            .iter()
+            .map(|s| s.len())
+            .max();
+        assert_eq!(
+            Some(graph.maximum_independent_set(None).set.len()),
+            expected
+        );
+    }
+    #[test]
+    fn test_independent_sets_deep() {
+        // a perfect matching: every maximal independent set takes one end of each edge,
+        // far deeper than a recursive search could go
+        let n_nodes = 20000;
+        let edges: Vec<(usize, usize)> = (0..n_nodes / 2).map(|i| (2 * i, 2 * i + 1)).collect();
+        let graph = graph_from_edges(&edges, n_nodes);
+        let mut first = Vec::new();
+        let flow = graph.visit_independent_sets(&mut |set: &[usize]| {
+            first = set.to_vec();
+            ControlFlow::Break(())
+        });
+        assert!(flow.is_break());
+        assert_eq!(first.len(), n_nodes / 2);
+        first.sort_unstable();
+        assert!(first.iter().enumerate().all(|(i, &v)| v / 2 == i));
+
+        let best = graph.maximum_independent_set(None);
+        assert!(best.optimal);
+        assert_eq!(best.set.len(), n_nodes / 2);
+    }
+}
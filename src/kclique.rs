@@ -0,0 +1,220 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::visitor::CliqueVisitor;
+use std::ops::ControlFlow;
+
+/// Out-neighbourhood of `v` in the degeneracy-oriented DAG, relabelled locally and kept in
+/// DAG order, along with the local out-neighbourhoods of its members. At most d vertices
+/// for degeneracy d, so every candidate set below `v` fits in a d-bit Bitvec.
+struct LocalDag {
+    vertices: Vec<usize>,
+    out: Vec<Bitvec>,
+}
+
+impl LocalDag {
+    fn new(graph: &Bitmatrix, v: usize, position: &[usize]) -> Self {
+        let mut vertices: Vec<usize> = graph
+            .get_neighbours(v)
+            .into_iter()
+            .filter(|&w| position[w] > position[v])
+            .collect();
+        vertices.sort_unstable_by_key(|&w| position[w]);
+        let out = (0..vertices.len())
+            .map(|i| {
+                let later: Vec<usize> = (i + 1..vertices.len())
+                    .filter(|&j| graph.matrix[vertices[i]].contains(vertices[j]))
+                    .collect();
+                Bitvec::from_vector(&later, vertices.len())
+            })
+            .collect();
+        LocalDag { vertices, out }
+    }
+
+    fn all(&self) -> Bitvec {
+        Bitvec::from_vector(
+            &(0..self.vertices.len()).collect::<Vec<usize>>(),
+            self.vertices.len(),
+        )
+    }
+
+    /// Number of k-cliques extending a clique of `size` vertices by members of `cand`.
+    fn count(&self, size: usize, k: usize, cand: &Bitvec) -> u64 {
+        if size + 1 == k {
+            return cand.n_elements() as u64;
+        }
+        if size + cand.n_elements() < k {
+            return 0;
+        }
+        cand.elements()
+            .into_iter()
+            .map(|u| self.count(size + 1, k, &cand.intersection(&self.out[u])))
+            .sum()
+    }
+
+    /// Adds the number of cliques of every size from `size + 1` up to `counts.len()`.
+    fn count_all(&self, size: usize, cand: &Bitvec, counts: &mut [u64]) {
+        let n_cand = cand.n_elements() as u64;
+        if size < counts.len() {
+            counts[size] += n_cand;
+        }
+        if size + 1 >= counts.len() {
+            return;
+        }
+        for u in cand.elements() {
+            let next = cand.intersection(&self.out[u]);
+            if !next.is_empty() {
+                self.count_all(size + 1, &next, counts);
+            }
+        }
+    }
+
+    fn visit<V: CliqueVisitor>(
+        &self,
+        clique: &mut Vec<usize>,
+        k: usize,
+        cand: &Bitvec,
+        visitor: &mut V,
+    ) -> ControlFlow<()> {
+        if clique.len() + cand.n_elements() < k {
+            return ControlFlow::Continue(());
+        }
+        for u in cand.elements() {
+            clique.push(self.vertices[u]);
+            let flow = if clique.len() == k {
+                visitor.visit(clique)
+            } else {
+                self.visit(clique, k, &cand.intersection(&self.out[u]), visitor)
+            };
+            clique.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Number of k-cliques (k >= 2) whose earliest vertex along the degeneracy ordering
+/// (given as `position`) is `v`. Summed over every `v`, this counts each k-clique once.
+pub(crate) fn count_k_cliques_from(
+    graph: &Bitmatrix,
+    v: usize,
+    k: usize,
+    position: &[usize],
+) -> u64 {
+    let dag = LocalDag::new(graph, v, position);
+    dag.count(1, k, &dag.all())
+}
+
+/// Visits the k-cliques (k >= 1) counted by `count_k_cliques_from`.
+pub(crate) fn visit_k_cliques_from<V: CliqueVisitor>(
+    graph: &Bitmatrix,
+    v: usize,
+    k: usize,
+    position: &[usize],
+    visitor: &mut V,
+) -> ControlFlow<()> {
+    let mut clique = Vec::with_capacity(k);
+    clique.push(v);
+    if k == 1 {
+        return visitor.visit(&clique);
+    }
+    let dag = LocalDag::new(graph, v, position);
+    dag.visit(&mut clique, k, &dag.all(), visitor)
+}
+
+/// Fixed-size clique listing and counting in the style of kClist (Danisch et al.).
+/// Edges are oriented along the degeneracy ordering, so each k-clique is reached exactly
+/// once, from its first vertex, by recursing through shrinking out-neighbourhood sets.
+/// Nothing but the current path is stored, unlike building every level with
+/// `NextCliques`.
+pub trait KCliques {
+    fn count_k_cliques(&self, k: usize) -> u64;
+    /// Counts the cliques of every size 1..=k in a single pass; entry `i` holds the
+    /// number of cliques with `i + 1` vertices.
+    fn count_cliques_up_to(&self, k: usize) -> Vec<u64>;
+    fn visit_k_cliques<V: CliqueVisitor>(&self, k: usize, visitor: &mut V) -> ControlFlow<()>;
+    fn k_cliques(&self, k: usize) -> Vec<Vec<usize>>;
+}
+
+impl KCliques for Bitmatrix {
+    fn count_k_cliques(&self, k: usize) -> u64 {
+        match k {
+            0 => 0,
+            1 => self.n_nodes() as u64,
+            _ => {
+                let ordering = self.degeneracy_ordering();
+                (0..self.n_nodes())
+                    .map(|v| count_k_cliques_from(self, v, k, &ordering.position))
+                    .sum()
+            }
+        }
+    }
+
+    fn count_cliques_up_to(&self, k: usize) -> Vec<u64> {
+        let mut counts = vec![0; k];
+        if k == 0 {
+            return counts;
+        }
+        counts[0] = self.n_nodes() as u64;
+        let ordering = self.degeneracy_ordering();
+        for v in 0..self.n_nodes() {
+            let dag = LocalDag::new(self, v, &ordering.position);
+            dag.count_all(1, &dag.all(), &mut counts);
+        }
+        counts
+    }
+
+    fn visit_k_cliques<V: CliqueVisitor>(&self, k: usize, visitor: &mut V) -> ControlFlow<()> {
+        if k == 0 {
+            return ControlFlow::Continue(());
+        }
+        let ordering = self.degeneracy_ordering();
+        for v in 0..self.n_nodes() {
+            visit_k_cliques_from(self, v, k, &ordering.position, visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn k_cliques(&self, k: usize) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        let _ = self.visit_k_cliques(k, &mut |clique: &[usize]| {
+            let mut clique = clique.to_vec();
+            clique.sort_unstable();
+            cliques.push(clique);
+            ControlFlow::Continue(())
+        });
+        cliques
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::pseudo_random_graph;
+
+    #[test]
+    fn test_k_cliques_of_complete_graph() {
+        // K6 has C(6, k) cliques of size k
+        let rows: Vec<Vec<usize>> = (0..6)
+            .map(|u| (0..6).filter(|&v| v != u).collect())
+            .collect();
+        let graph = Bitmatrix::new(rows, 6);
+        assert_eq!(graph.count_cliques_up_to(7), vec![6, 15, 20, 15, 6, 1, 0]);
+        assert_eq!(graph.count_k_cliques(3), 20);
+        assert_eq!(graph.count_k_cliques(7), 0);
+        let mut triangles = graph.k_cliques(3);
+        triangles.sort();
+        triangles.dedup();
+        assert_eq!(triangles.len(), 20);
+    }
+    #[test]
+    fn test_k_cliques_match_counts() {
+        let n_nodes = 30;
+        let graph = pseudo_random_graph(n_nodes, 11, 4, 2);
+        let counts = graph.count_cliques_up_to(6);
+        for k in 1..=6 {
+            assert_eq!(graph.count_k_cliques(k), counts[k - 1]);
+            assert_eq!(graph.k_cliques(k).len() as u64, counts[k - 1]);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::search::SearchStats;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+/// Cliques handed out per chunk by the streaming level generator.
+pub const LEVEL_CHUNK: usize = 1 << 14;
+
+/// One level of the clique complex: cliques of the same size stored as sorted vertex
+/// ids, back to back in a single buffer. Levels built by extending a sorted level are
+/// themselves in lexicographic order, which `position` relies on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CliqueLevel {
+    size: usize,
+    ids: Vec<usize>,
+}
+
+impl CliqueLevel {
+    pub fn new(size: usize) -> Self {
+        CliqueLevel {
+            size,
+            ids: Vec::new(),
+        }
+    }
+    /// The level of single vertices `0..n_nodes`.
+    pub fn singletons(n_nodes: usize) -> Self {
+        CliqueLevel {
+            size: 1,
+            ids: (0..n_nodes).collect(),
+        }
+    }
+    /// Number of vertices in each clique of the level.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    pub fn len(&self) -> usize {
+        self.ids.len() / self.size.max(1)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+    pub fn get(&self, index: usize) -> &[usize] {
+        &self.ids[index * self.size..(index + 1) * self.size]
+    }
+    pub fn iter(&self) -> std::slice::ChunksExact<'_, usize> {
+        self.ids.chunks_exact(self.size.max(1))
+    }
+    pub fn append(&mut self, other: &mut CliqueLevel) {
+        debug_assert_eq!(other.size, self.size);
+        self.ids.append(&mut other.ids);
+    }
+    /// Index of `clique` in a lexicographically sorted level, by binary search.
+    pub fn position(&self, clique: &[usize]) -> Option<usize> {
+        let len = self.len();
+        let index = partition_point(len, |i| self.get(i) < clique);
+        (index < len && self.get(index) == clique).then_some(index)
+    }
+}
+
+/// First index in `0..len` for which `before` is false, `before` being true on a prefix.
+fn partition_point(len: usize, before: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if before(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Streams the next level of the clique complex in chunks of about `chunk_size`
+/// cliques. Each clique only grows by common neighbours larger than its last vertex, so
+/// every clique of the next level comes from exactly one clique of this one and chunks
+/// can be processed (and dropped) independently.
+pub struct LevelChunks<'a> {
+    graph: &'a Bitmatrix,
+    level: &'a CliqueLevel,
+    next: usize,
+    chunk_size: usize,
+    stats: SearchStats,
+}
+
+impl<'a> LevelChunks<'a> {
+    pub fn new(graph: &'a Bitmatrix, level: &'a CliqueLevel, chunk_size: usize) -> Self {
+        LevelChunks {
+            graph,
+            level,
+            next: 0,
+            chunk_size: chunk_size.max(1),
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// The cliques extended so far as nodes of a search tree, each with its extensions
+    /// as candidates. No pivots are involved.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+impl Iterator for LevelChunks<'_> {
+    type Item = CliqueLevel;
+
+    fn next(&mut self) -> Option<CliqueLevel> {
+        let mut chunk = CliqueLevel::new(self.level.size() + 1);
+        while chunk.len() < self.chunk_size && self.next < self.level.len() {
+            let clique = self.level.get(self.next);
+            self.next += 1;
+            let (&first, rest) = match clique.split_first() {
+                Some(split) => split,
+                None => continue,
+            };
+            let mut common_neighbours = self.graph.matrix[first].clone();
+            for &vertex in rest {
+                common_neighbours.intersection_with(&self.graph.matrix[vertex]);
+            }
+            let extensions = common_neighbours.elements_from(clique[clique.len() - 1]);
+            self.stats.record_node(clique.len(), extensions.len());
+            for neighbour in extensions {
+                chunk.ids.extend_from_slice(clique);
+                chunk.ids.push(neighbour);
+            }
+        }
+        (!chunk.is_empty()).then_some(chunk)
+    }
+}
+
+/// Walks the whole clique complex depth first, one chunk at a time: each chunk of a
+/// level is extended to the next level before the rest of its own level is built, so
+/// only one chunk per level is ever held in memory. `visit` sees every chunk together
+/// with the time spent building it. The shape of the walk is added to `stats` (see
+/// `LevelChunks::stats`).
+pub fn visit_levels(
+    graph: &Bitmatrix,
+    chunk_size: usize,
+    stats: &mut SearchStats,
+    visit: &mut dyn FnMut(&CliqueLevel, Duration) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let n_nodes = graph.n_nodes();
+    let chunk_size = chunk_size.max(1);
+    for start in (0..n_nodes).step_by(chunk_size) {
+        let start_time = Instant::now();
+        let chunk = CliqueLevel {
+            size: 1,
+            ids: (start..n_nodes.min(start + chunk_size)).collect(),
+        };
+        visit(&chunk, start_time.elapsed())?;
+        descend(graph, &chunk, chunk_size, stats, visit)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn descend(
+    graph: &Bitmatrix,
+    level: &CliqueLevel,
+    chunk_size: usize,
+    stats: &mut SearchStats,
+    visit: &mut dyn FnMut(&CliqueLevel, Duration) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let mut chunks = LevelChunks::new(graph, level, chunk_size);
+    let flow = loop {
+        let start_time = Instant::now();
+        let chunk = match chunks.next() {
+            Some(chunk) => chunk,
+            None => break ControlFlow::Continue(()),
+        };
+        let mut flow = visit(&chunk, start_time.elapsed());
+        if flow.is_continue() {
+            flow = descend(graph, &chunk, chunk_size, stats, visit);
+        }
+        if flow.is_break() {
+            break flow;
+        }
+    };
+    stats.merge(&chunks.stats(), 0);
+    flow
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_chunks() {
+        // K4 on 0..4 plus the edge 4-5
+        let rows = vec![
+            vec![1, 2, 3],
+            vec![0, 2, 3],
+            vec![0, 1, 3],
+            vec![0, 1, 2],
+            vec![5],
+            vec![4],
+        ];
+        let graph = Bitmatrix::new(rows, 6);
+        let vertices = CliqueLevel::singletons(6);
+        let edges: Vec<CliqueLevel> = LevelChunks::new(&graph, &vertices, 2).collect();
+        assert!(edges.iter().all(|chunk| chunk.len() <= 3));
+        let mut all_edges = CliqueLevel::new(2);
+        for mut chunk in edges {
+            all_edges.append(&mut chunk);
+        }
+        assert_eq!(all_edges.len(), 7);
+        assert_eq!(all_edges.position(&[2, 3]), Some(5));
+        assert_eq!(all_edges.position(&[3, 4]), None);
+
+        let mut n_chunks = 0;
+        let mut counts = vec![0; 4];
+        let mut stats = SearchStats::default();
+        let _ = visit_levels(&graph, 2, &mut stats, &mut |chunk, _| {
+            n_chunks += 1;
+            counts[chunk.size() - 1] += chunk.len();
+            ControlFlow::Continue(())
+        });
+        assert_eq!(counts, vec![6, 7, 4, 1]);
+        assert!(n_chunks > 4);
+        assert_eq!(stats.nodes, 6 + 7 + 4 + 1);
+    }
+}
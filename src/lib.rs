@@ -1,52 +1,1607 @@
+mod bipartite;
 mod bitmatrix;
 mod bitvec;
+mod checkpoint;
+mod cliquefile;
+mod cliquegraph;
+mod colouring;
+mod degeneracy;
+mod estimate;
 mod homology;
+mod independent;
+mod kclique;
+mod levels;
+mod limits;
 mod linalg;
-use bitmatrix::{AllCliques, Bitmatrix, NextCliques};
-use bitvec::Bitvec;
+mod local;
+mod maximum;
+mod parallel;
+mod percolation;
+mod random;
+mod relaxed;
+mod search;
+#[cfg(test)]
+mod testutil;
+mod visitor;
+use bipartite::{Bicliques, BipartiteMatrix};
+use bitmatrix::Bitmatrix;
+use checkpoint::{CheckpointPolicy, CliqueCheckpoint, KCliqueCheckpoint, Resumable};
+use cliquefile::{CliqueBuffer, CliqueFile, CliqueFileHeader, CliqueFormat, CliqueWriter};
+use cliquegraph::CliqueGraphs;
+use colouring::{Colour, ColourOrder};
+use degeneracy::Degeneracy;
+use estimate::CliqueEstimates;
+use homology::HomologyGroup;
+use independent::IndependentSets;
+use kclique::KCliques;
+use limits::{LimitedCliques, Limits, Outcome, StopReason};
+use linalg::PrimeField;
+use local::LocalCliques;
+use maximum::{MaxClique, MaxWeightClique, Weight};
+use parallel::ParallelCliques;
+use percolation::CliquePercolation;
+use pyo3::exceptions::{PyOverflowError, PyValueError};
 use pyo3::prelude::*;
-use std::time::Instant;
+use pyo3::types::PyTuple;
+use relaxed::RelaxedCliques;
+use search::{CliqueSearch, Cliques, PivotStrategy, SearchStats};
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+/// Betti numbers of the clique complex with coefficients in Z/`field`, which must be a
+/// prime. The list has one entry per dimension, from 0 up to and including the top one
+/// (the size of the largest clique minus one), so cycles made of top-dimensional
+/// simplices are counted too: the octahedron, a hollow sphere, gives `[1, 0, 1]`.
+/// Ctrl-C raises `KeyboardInterrupt`.
 #[pyfunction]
-fn betti_numbers(adjacency_matrix: Vec<Vec<usize>>) -> PyResult<Vec<usize>> {
+#[pyo3(signature = (adjacency_matrix, field = 2))]
+fn betti_numbers(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    field: u64,
+) -> PyResult<Vec<usize>> {
+    let (betti, _) = betti_numbers_limited(py, adjacency_matrix, None, None, field)?;
+    Ok(betti)
+}
+
+/// Same as `betti_numbers`, stopping after dimension `max_dimension` or once
+/// `time_limit` seconds have passed. Returns `(betti, stopped)`: `stopped` is `None` when
+/// every dimension was computed, otherwise the reason (`"max_dimension"` or
+/// `"time_budget"`) the list was cut short.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, max_dimension = None, time_limit = None, field = 2))]
+fn betti_numbers_limited(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    max_dimension: Option<usize>,
+    time_limit: Option<f64>,
+    field: u64,
+) -> PyResult<(Vec<usize>, Option<&'static str>)> {
+    let field = PrimeField::new(field).ok_or_else(|| {
+        PyValueError::new_err(format!("field must be a prime below 2^32, got {}", field))
+    })?;
+    let limits = Limits {
+        max_dimension,
+        time_budget: parse_time_limit(time_limit)?,
+        ..Limits::default()
+    };
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let (betti, stopped) = interruptible(py, |cancel| {
+        homology::betti_numbers_over(&graph, field, &limits, &mut || {
+            cancel.load(Ordering::Relaxed)
+        })
+    })?;
+    Ok((betti, stop_name(stopped)))
+}
+
+/// Integral homology of the clique complex. Returns `(homology, stopped)`, where
+/// `homology[k]` is `(rank, torsion)` for H_k: the free rank and the torsion coefficients,
+/// each dividing the next. `stopped` is as for `betti_numbers_limited`. Raises
+/// `OverflowError` if the coefficients outgrow 64 bits during the elimination.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, max_dimension = None, time_limit = None))]
+fn integral_homology(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    max_dimension: Option<usize>,
+    time_limit: Option<f64>,
+) -> PyResult<(Vec<HomologyGroup>, Option<&'static str>)> {
+    let limits = Limits {
+        max_dimension,
+        time_budget: parse_time_limit(time_limit)?,
+        ..Limits::default()
+    };
     let n_nodes = adjacency_matrix.len();
     let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
-    Ok(homology::betti_numbers(graph))
+    let result = interruptible(py, |cancel| {
+        homology::integral_homology_limited(&graph, &limits, &mut || cancel.load(Ordering::Relaxed))
+    })?;
+    let (homology, stopped) = result.map_err(|e| PyOverflowError::new_err(e.to_string()))?;
+    Ok((homology, stop_name(stopped)))
+}
+
+/// Maximal clique algorithms selectable from Python through the `algorithm` keyword.
+/// "pivot" runs the pivoting search over the whole graph, "degeneracy" runs the
+/// Eppstein–Löffler–Strash variant, which is much faster on large sparse graphs.
+enum Algorithm {
+    Pivot,
+    Degeneracy,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "pivot" => Ok(Algorithm::Pivot),
+            "degeneracy" => Ok(Algorithm::Degeneracy),
+            _ => Err(PyValueError::new_err(format!(
+                "unknown algorithm '{}', expected 'pivot' or 'degeneracy'",
+                name
+            ))),
+        }
+    }
+
+    /// Folds every clique into an accumulator, on a pool of `threads` workers when given
+    /// (0 meaning one per core) and sequentially otherwise, until a limit is hit or
+    /// `cancel` is set. The outcome also reports the shape of the search tree.
+    #[allow(clippy::too_many_arguments)]
+    fn fold<T, F, R>(
+        &self,
+        graph: &Bitmatrix,
+        pivot: Option<PivotStrategy>,
+        threads: Option<usize>,
+        limits: &Limits,
+        cancel: &AtomicBool,
+        fold: F,
+        reduce: R,
+    ) -> (T, Outcome)
+    where
+        T: Send + Default,
+        F: Fn(&mut T, &[usize]) -> ControlFlow<()> + Sync,
+        R: Fn(T, T) -> T + Sync,
+    {
+        let (sparse, default_pivot) = match self {
+            Algorithm::Pivot => (false, PivotStrategy::Degree),
+            Algorithm::Degeneracy => (true, PivotStrategy::Tomita),
+        };
+        let pivot = pivot.unwrap_or(default_pivot);
+        match threads {
+            Some(threads) => {
+                graph.par_fold_cliques_limited(sparse, pivot, threads, limits, cancel, fold, reduce)
+            }
+            None => {
+                let mut acc = T::default();
+                let outcome = graph.visit_cliques_limited(
+                    sparse,
+                    pivot,
+                    limits,
+                    &mut || cancel.load(Ordering::Relaxed),
+                    &mut |clique: &[usize]| fold(&mut acc, clique),
+                );
+                (acc, outcome)
+            }
+        }
+    }
+}
+
+/// How often a running job looks for Ctrl-C.
+const SIGNAL_POLL: Duration = Duration::from_millis(50);
+
+/// Runs `job` on a helper thread with the GIL released while this thread watches for
+/// signals. A pending signal (Ctrl-C) sets the flag handed to `job`, which is expected to
+/// wind down promptly, and its `KeyboardInterrupt` is raised once it has.
+fn interruptible<T: Send>(
+    py: Python<'_>,
+    job: impl FnOnce(&AtomicBool) -> T + Send,
+) -> PyResult<T> {
+    let cancel = &AtomicBool::new(false);
+    py.allow_threads(|| {
+        std::thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let worker = scope.spawn(move || {
+                let _ = sender.send(job(cancel));
+            });
+            let mut interrupted = None;
+            loop {
+                match receiver.recv_timeout(SIGNAL_POLL) {
+                    Ok(result) => {
+                        return match interrupted {
+                            Some(err) => Err(err),
+                            None => Ok(result),
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        if interrupted.is_none() {
+                            if let Err(err) = Python::with_gil(|py| py.check_signals()) {
+                                cancel.store(true, Ordering::Relaxed);
+                                interrupted = Some(err);
+                            }
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => match worker.join() {
+                        Err(panic) => std::panic::resume_unwind(panic),
+                        Ok(()) => unreachable!("the worker always sends its result"),
+                    },
+                }
+            }
+        })
+    })
+}
+
+fn parse_time_limit(time_limit: Option<f64>) -> PyResult<Option<Duration>> {
+    time_limit
+        .map(|seconds| {
+            Duration::try_from_secs_f64(seconds)
+                .map_err(|_| PyValueError::new_err(format!("invalid time_limit {}", seconds)))
+        })
+        .transpose()
+}
+
+/// Enumeration limits from the Python keywords; `time_limit` is in seconds.
+fn parse_limits(
+    max_results: Option<u64>,
+    min_size: usize,
+    max_size: Option<usize>,
+    time_limit: Option<f64>,
+) -> PyResult<Limits> {
+    Ok(Limits {
+        max_results,
+        min_size,
+        max_size,
+        time_budget: parse_time_limit(time_limit)?,
+        max_dimension: None,
+    })
+}
+
+fn stop_name(stopped: Option<StopReason>) -> Option<&'static str> {
+    stopped.map(|reason| reason.as_str())
+}
+
+/// Pivot strategies selectable through the `pivot` keyword; `None` keeps the algorithm's
+/// own default. `seed` only matters for the random strategy.
+fn parse_pivot(name: Option<&str>, seed: u64) -> PyResult<Option<PivotStrategy>> {
+    match name {
+        None => Ok(None),
+        Some("degree") => Ok(Some(PivotStrategy::Degree)),
+        Some("tomita") => Ok(Some(PivotStrategy::Tomita)),
+        Some("random") => Ok(Some(PivotStrategy::Random(seed))),
+        Some("none") => Ok(Some(PivotStrategy::NoPivot)),
+        Some(name) => Err(PyValueError::new_err(format!(
+            "unknown pivot '{}', expected 'degree', 'tomita', 'random' or 'none'",
+            name
+        ))),
+    }
+}
+
+/// Result of `find_cliques` and `cliques_up_to`. `count` is the number of cliques and
+/// `counts[i]` the number with `i + 1` vertices; `elapsed_ns` is the duration of the run
+/// and `timings_ns[i]`, when filled in, the time spent on the cliques with `i + 1`
+/// vertices. `stopped` names the limit that cut the run short, if any. The other fields
+/// describe the search tree: the branch `nodes` visited, the `max_depth` reached, the
+/// `pivot_choices` made and `peak_candidates`, the largest candidate set of any node.
+#[pyclass(get_all)]
+struct EnumerationResult {
+    count: u64,
+    counts: Vec<u64>,
+    elapsed_ns: u64,
+    timings_ns: Vec<u64>,
+    stopped: Option<&'static str>,
+    nodes: u64,
+    max_depth: usize,
+    pivot_choices: u64,
+    peak_candidates: usize,
+}
+
+impl EnumerationResult {
+    fn new(counts: Vec<u64>, elapsed: Duration, outcome: Outcome) -> Self {
+        let stats = outcome.stats;
+        EnumerationResult {
+            count: outcome.results,
+            counts,
+            elapsed_ns: elapsed.as_nanos() as u64,
+            timings_ns: Vec::new(),
+            stopped: stop_name(outcome.stopped),
+            nodes: stats.nodes,
+            max_depth: stats.max_depth,
+            pivot_choices: stats.pivot_choices,
+            peak_candidates: stats.peak_candidates,
+        }
+    }
+}
+
+#[pymethods]
+impl EnumerationResult {
+    fn __repr__(&self) -> String {
+        let stopped = match self.stopped {
+            Some(reason) => format!("'{}'", reason),
+            None => "None".to_string(),
+        };
+        format!(
+            "EnumerationResult(count={}, elapsed_ns={}, stopped={}, nodes={}, max_depth={}, \
+             pivot_choices={}, peak_candidates={})",
+            self.count,
+            self.elapsed_ns,
+            stopped,
+            self.nodes,
+            self.max_depth,
+            self.pivot_choices,
+            self.peak_candidates
+        )
+    }
 }
 
+/// Counts the maximal cliques, returning an `EnumerationResult` where `counts` is the
+/// histogram of their sizes. With `threads` set, the enumeration runs on that many
+/// workers (0 meaning one per core). Only cliques with `min_size` to `max_size` vertices
+/// are counted; the run stops after `max_results` of them or `time_limit` seconds, and
+/// `stopped` then names the limit that cut it short. Ctrl-C raises `KeyboardInterrupt`.
 #[pyfunction]
-fn find_cliques(adjacency_matrix: Vec<Vec<usize>>) -> PyResult<(f64, u32)> {
+#[pyo3(signature = (
+    adjacency_matrix,
+    algorithm = "pivot",
+    pivot = None,
+    seed = 0,
+    threads = None,
+    max_results = None,
+    min_size = 0,
+    max_size = None,
+    time_limit = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn find_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    algorithm: &str,
+    pivot: Option<&str>,
+    seed: u64,
+    threads: Option<usize>,
+    max_results: Option<u64>,
+    min_size: usize,
+    max_size: Option<usize>,
+    time_limit: Option<f64>,
+) -> PyResult<EnumerationResult> {
+    let algorithm = Algorithm::parse(algorithm)?;
+    let pivot = parse_pivot(pivot, seed)?;
+    let limits = parse_limits(max_results, min_size, max_size, time_limit)?;
     let n_nodes = adjacency_matrix.len();
     let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
     let start_time = Instant::now();
-    let count = graph.all_cliques();
-    let elapsed = start_time.elapsed();
-    Ok((elapsed.as_secs() as f64, count as u32))
+    let (histogram, outcome) = interruptible(py, |cancel| {
+        algorithm.fold(
+            &graph,
+            pivot,
+            threads,
+            &limits,
+            cancel,
+            |histogram: &mut Vec<u64>, clique| {
+                bitmatrix::add_to_histogram(histogram, clique.len());
+                ControlFlow::Continue(())
+            },
+            bitmatrix::merge_histograms,
+        )
+    })?;
+    Ok(EnumerationResult::new(
+        histogram,
+        start_time.elapsed(),
+        outcome,
+    ))
+}
+
+/// Lists the maximal cliques as `(cliques, stopped)`, with the same options and
+/// truncation report as `find_cliques`.
+#[pyfunction]
+#[pyo3(signature = (
+    adjacency_matrix,
+    algorithm = "pivot",
+    pivot = None,
+    seed = 0,
+    threads = None,
+    max_results = None,
+    min_size = 0,
+    max_size = None,
+    time_limit = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn maximal_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    algorithm: &str,
+    pivot: Option<&str>,
+    seed: u64,
+    threads: Option<usize>,
+    max_results: Option<u64>,
+    min_size: usize,
+    max_size: Option<usize>,
+    time_limit: Option<f64>,
+) -> PyResult<(Vec<Vec<usize>>, Option<&'static str>)> {
+    let algorithm = Algorithm::parse(algorithm)?;
+    let pivot = parse_pivot(pivot, seed)?;
+    let limits = parse_limits(max_results, min_size, max_size, time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let (cliques, outcome) = interruptible(py, |cancel| {
+        algorithm.fold(
+            &graph,
+            pivot,
+            threads,
+            &limits,
+            cancel,
+            |cliques: &mut Vec<Vec<usize>>, clique| {
+                let mut clique = clique.to_vec();
+                clique.sort_unstable();
+                cliques.push(clique);
+                ControlFlow::Continue(())
+            },
+            |mut a, mut b| {
+                a.append(&mut b);
+                a
+            },
+        )
+    })?;
+    Ok((cliques, stop_name(outcome.stopped)))
+}
+
+/// Forwards cliques to a Python callable, taking the GIL for each call so it can be used
+/// from worker threads. The callable stops the enumeration by returning `False`; any
+/// other return value (including `None`) lets it continue. The first exception raised
+/// stops the enumeration and is kept to be re-raised.
+struct PyCallback {
+    callback: PyObject,
+    error: Mutex<Option<PyErr>>,
+}
+
+impl PyCallback {
+    fn call(&self, clique: &[usize]) -> ControlFlow<()> {
+        let mut clique = clique.to_vec();
+        clique.sort_unstable();
+        self.invoke((clique,))
+    }
+
+    fn invoke(&self, args: impl IntoPy<Py<PyTuple>>) -> ControlFlow<()> {
+        Python::with_gil(|py| match self.callback.call1(py, args) {
+            Ok(result) => match result.extract::<bool>(py) {
+                Ok(false) => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            },
+            Err(err) => {
+                self.error.lock().unwrap().get_or_insert(err);
+                ControlFlow::Break(())
+            }
+        })
+    }
+}
+
+/// Calls `callback(clique)` for every maximal clique. Returns `None` if the enumeration
+/// ran to completion, `"visitor"` if the callback stopped it, or the name of the limit
+/// that did, the limits being those of `find_cliques`. With `threads` set, the callback
+/// is invoked from several worker threads, in no particular order.
+#[pyfunction]
+#[pyo3(signature = (
+    adjacency_matrix,
+    callback,
+    algorithm = "pivot",
+    pivot = None,
+    seed = 0,
+    threads = None,
+    max_results = None,
+    min_size = 0,
+    max_size = None,
+    time_limit = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn visit_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    callback: PyObject,
+    algorithm: &str,
+    pivot: Option<&str>,
+    seed: u64,
+    threads: Option<usize>,
+    max_results: Option<u64>,
+    min_size: usize,
+    max_size: Option<usize>,
+    time_limit: Option<f64>,
+) -> PyResult<Option<&'static str>> {
+    let algorithm = Algorithm::parse(algorithm)?;
+    let pivot = parse_pivot(pivot, seed)?;
+    let limits = parse_limits(max_results, min_size, max_size, time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let callback = PyCallback {
+        callback,
+        error: Mutex::new(None),
+    };
+    let (_, outcome) = interruptible(py, |cancel| {
+        algorithm.fold(
+            &graph,
+            pivot,
+            threads,
+            &limits,
+            cancel,
+            |_: &mut (), clique| callback.call(clique),
+            |_, _| (),
+        )
+    })?;
+    match callback.error.into_inner().unwrap() {
+        Some(err) => Err(err),
+        None => Ok(stop_name(outcome.stopped)),
+    }
+}
+
+/// Lazy iterator over maximal cliques. Only the search state is kept between calls,
+/// so memory stays bounded and a `for` loop can break out part-way.
+#[pyclass]
+struct CliqueIterator {
+    cliques: Cliques,
+}
+
+#[pymethods]
+impl CliqueIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Vec<usize>> {
+        slf.cliques.next()
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, pivot = None, seed = 0))]
+fn iter_cliques(
+    adjacency_matrix: Vec<Vec<usize>>,
+    pivot: Option<&str>,
+    seed: u64,
+) -> PyResult<CliqueIterator> {
+    let pivot = parse_pivot(pivot, seed)?.unwrap_or_default();
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(CliqueIterator {
+        cliques: Cliques::new(graph, pivot),
+    })
+}
+
+/// Encoded cliques a task keeps to itself before appending them to the shared file.
+const WRITE_BUFFER_BYTES: usize = 1 << 16;
+
+/// Streams the maximal cliques to the file at `path` instead of returning them, for
+/// enumerations too large to hold in memory. `format` is `"binary"` (compact varint
+/// records) or `"text"` (a line of vertices per clique); either way the file starts with
+/// the vertex count and the parameters below, and can be read back with `read_cliques`.
+/// The other options and the `(count, stopped)` result are those of `find_cliques`.
+#[pyfunction]
+#[pyo3(signature = (
+    adjacency_matrix,
+    path,
+    format = "binary",
+    algorithm = "pivot",
+    pivot = None,
+    seed = 0,
+    threads = None,
+    max_results = None,
+    min_size = 0,
+    max_size = None,
+    time_limit = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn write_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    path: PathBuf,
+    format: &str,
+    algorithm: &str,
+    pivot: Option<&str>,
+    seed: u64,
+    threads: Option<usize>,
+    max_results: Option<u64>,
+    min_size: usize,
+    max_size: Option<usize>,
+    time_limit: Option<f64>,
+) -> PyResult<(u64, Option<&'static str>)> {
+    let file_format = match format {
+        "binary" => CliqueFormat::Binary,
+        "text" => CliqueFormat::Text,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown format '{}', expected 'binary' or 'text'",
+                format
+            )))
+        }
+    };
+    let algorithm_name = algorithm;
+    let algorithm = Algorithm::parse(algorithm)?;
+    let pivot_name = pivot;
+    let pivot = parse_pivot(pivot, seed)?;
+    let limits = parse_limits(max_results, min_size, max_size, time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+    let params = [
+        ("algorithm", algorithm_name.to_string()),
+        ("pivot", pivot_name.unwrap_or("default").to_string()),
+        ("seed", seed.to_string()),
+        ("min_size", min_size.to_string()),
+        ("max_size", optional(max_size.map(|max| max.to_string()))),
+        (
+            "max_results",
+            optional(max_results.map(|max| max.to_string())),
+        ),
+        (
+            "time_limit",
+            optional(time_limit.map(|secs| secs.to_string())),
+        ),
+    ];
+    let header = CliqueFileHeader {
+        n_nodes,
+        params: params
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    };
+    // every task encodes into its own buffer and only locks the file to append a full one
+    let writer = Mutex::new(CliqueWriter::create(&path, &header, file_format)?);
+    let (mut rest, outcome) = interruptible(py, |cancel| {
+        algorithm.fold(
+            &graph,
+            pivot,
+            threads,
+            &limits,
+            cancel,
+            |buffer: &mut CliqueBuffer, clique| {
+                buffer.push(file_format, clique);
+                if buffer.n_bytes() < WRITE_BUFFER_BYTES {
+                    return ControlFlow::Continue(());
+                }
+                writer.lock().unwrap().visit_buffer(buffer)
+            },
+            |mut left, mut right| {
+                left.append(&mut right);
+                if left.n_bytes() >= WRITE_BUFFER_BYTES {
+                    let _ = writer.lock().unwrap().visit_buffer(&mut left);
+                }
+                left
+            },
+        )
+    })?;
+    let mut writer = writer.into_inner().unwrap();
+    let _ = writer.visit_buffer(&mut rest);
+    let count = writer.count();
+    writer.finish()?;
+    Ok((count, stop_name(outcome.stopped)))
+}
+
+/// Cliques read back from a file written by `write_cliques`. The file is memory-mapped
+/// and decoded as the iteration goes, so it may be larger than memory; it must not be
+/// changed while being read. `n_nodes`, `params` and `format` describe how it was
+/// written.
+#[pyclass]
+struct CliqueReader {
+    file: CliqueFile,
+    pos: usize,
+}
+
+#[pymethods]
+impl CliqueReader {
+    #[getter]
+    fn n_nodes(&self) -> usize {
+        self.file.header.n_nodes
+    }
+
+    #[getter]
+    fn params(&self) -> BTreeMap<String, String> {
+        self.file.header.params.iter().cloned().collect()
+    }
+
+    #[getter]
+    fn format(&self) -> &'static str {
+        match self.file.format {
+            CliqueFormat::Binary => "binary",
+            CliqueFormat::Text => "text",
+        }
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<Vec<usize>>> {
+        let this = &mut *slf;
+        this.file
+            .next_clique(&mut this.pos)
+            .transpose()
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+}
+
+/// Opens a clique file written by `write_cliques` for iteration.
+#[pyfunction]
+fn read_cliques(path: PathBuf) -> PyResult<CliqueReader> {
+    let file = CliqueFile::open(&path).map_err(file_error)?;
+    let pos = file.start();
+    Ok(CliqueReader { file, pos })
+}
+
+fn check_vertices(vertices: &[usize], n_nodes: usize) -> PyResult<()> {
+    match vertices.iter().find(|&&v| v >= n_nodes) {
+        Some(v) => Err(PyValueError::new_err(format!(
+            "vertex {} is out of range for a graph with {} vertices",
+            v, n_nodes
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Maximal cliques that contain every vertex of `include` and none of `exclude`.
+/// The cliques are maximal in the whole graph, excluded vertices included.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, include, exclude = Vec::new()))]
+fn cliques_containing(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    include: Vec<usize>,
+    exclude: Vec<usize>,
+) -> PyResult<Vec<Vec<usize>>> {
+    let n_nodes = adjacency_matrix.len();
+    check_vertices(&include, n_nodes)?;
+    check_vertices(&exclude, n_nodes)?;
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.cliques_containing(&include, &exclude)))
+}
+
+/// Maximal cliques containing vertex `v`.
+#[pyfunction]
+fn cliques_with_vertex(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    v: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    let n_nodes = adjacency_matrix.len();
+    check_vertices(&[v], n_nodes)?;
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.cliques_with_vertex(v)))
+}
+
+/// Maximal cliques containing the edge `(u, v)`.
+#[pyfunction]
+fn cliques_with_edge(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    u: usize,
+    v: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    let n_nodes = adjacency_matrix.len();
+    check_vertices(&[u, v], n_nodes)?;
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.cliques_with_edge(u, v)))
 }
 
+/// Returns the degeneracy ordering of the graph together with its degeneracy.
 #[pyfunction]
-fn cliques_up_to(adjacency_matrix: Vec<Vec<usize>>) -> PyResult<(f64, u32)> {
+fn degeneracy_ordering(adjacency_matrix: Vec<Vec<usize>>) -> PyResult<(Vec<usize>, usize)> {
     let n_nodes = adjacency_matrix.len();
-    let mut cliques = Vec::new();
-    let mut count = 0;
     let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
-    for i in 0..n_nodes {
-        cliques.push(Bitvec::from_vector(&[i], n_nodes))
+    let ordering = graph.degeneracy_ordering();
+    Ok((ordering.order, ordering.degeneracy))
+}
+
+/// Returns `(size, clique, optimal)` for a maximum clique. With `time_limit` (seconds)
+/// the search may stop early, returning the best clique found so far with
+/// `optimal = False`.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, time_limit = None))]
+fn maximum_clique(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    time_limit: Option<f64>,
+) -> PyResult<(usize, Vec<usize>, bool)> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let time_limit = parse_time_limit(time_limit)?;
+    let best = py.allow_threads(|| graph.maximum_clique(time_limit));
+    Ok((best.clique.len(), best.clique, best.optimal))
+}
+
+fn weighted_clique<W: Weight + IntoPy<PyObject> + Send + Sync>(
+    py: Python<'_>,
+    graph: &Bitmatrix,
+    weights: &[W],
+    time_limit: Option<Duration>,
+) -> PyResult<(PyObject, Vec<usize>, bool)> {
+    if weights.len() != graph.n_nodes() {
+        return Err(PyValueError::new_err(format!(
+            "expected {} weights, got {}",
+            graph.n_nodes(),
+            weights.len()
+        )));
     }
+    let best = py.allow_threads(|| graph.maximum_weight_clique(weights, time_limit));
+    Ok((best.weight.into_py(py), best.clique, best.optimal))
+}
+
+/// Returns `(weight, clique, optimal)` for a maximum weight clique, with one integer or
+/// float weight per vertex. `time_limit` behaves as in `maximum_clique`. Integer weights
+/// raise `OverflowError` if the positive ones sum past a 64-bit integer.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, weights, time_limit = None))]
+fn maximum_weight_clique(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    weights: &PyAny,
+    time_limit: Option<f64>,
+) -> PyResult<(PyObject, Vec<usize>, bool)> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let time_limit = parse_time_limit(time_limit)?;
+    match weights.extract::<Vec<i64>>() {
+        Ok(weights) => {
+            // every bound is at most the total positive weight, so that must fit
+            let total = weights
+                .iter()
+                .filter(|&&w| w > 0)
+                .try_fold(0i64, |total, &w| total.checked_add(w));
+            if total.is_none() {
+                return Err(PyOverflowError::new_err(
+                    "the positive weights must sum to less than 2**63",
+                ));
+            }
+            weighted_clique(py, &graph, &weights, time_limit)
+        }
+        Err(_) => weighted_clique(py, &graph, &weights.extract::<Vec<f64>>()?, time_limit),
+    }
+}
+
+/// Lists every clique with exactly `k` vertices.
+#[pyfunction]
+fn k_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.k_cliques(k)))
+}
+
+/// Counts the cliques with exactly `k` vertices.
+#[pyfunction]
+fn count_k_cliques(py: Python<'_>, adjacency_matrix: Vec<Vec<usize>>, k: usize) -> PyResult<u64> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.count_k_cliques(k)))
+}
+
+/// Counts the cliques of every size from 1 to `k` in one pass; entry `i` of the result is
+/// the number of cliques with `i + 1` vertices.
+#[pyfunction]
+fn count_cliques_up_to(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k: usize,
+) -> PyResult<Vec<u64>> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.count_cliques_up_to(k)))
+}
+
+/// Sampling estimate of the number of cliques with exactly `k` vertices, for graphs too
+/// large to count exactly. Returns `(estimate, (lower, upper), samples)` with a 95%
+/// confidence interval; when the count comes out exact without sampling, the interval is
+/// the count itself and `samples` is 0. `samples` must be positive.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, k, samples = 10000, seed = 0))]
+fn estimate_k_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k: usize,
+    samples: u64,
+    seed: u64,
+) -> PyResult<(f64, (f64, f64), u64)> {
+    if samples == 0 {
+        return Err(PyValueError::new_err("samples must be positive"));
+    }
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let estimate = py.allow_threads(|| graph.estimate_k_cliques(k, samples, seed));
+    Ok((
+        estimate.estimate,
+        (estimate.lower, estimate.upper),
+        estimate.samples,
+    ))
+}
+
+/// Sampling estimate of the number of maximal cliques, returned like
+/// `estimate_k_cliques`. The interval comes from the spread of the samples, so `samples`
+/// must be at least 2.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, samples = 10000, seed = 0))]
+fn estimate_maximal_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    samples: u64,
+    seed: u64,
+) -> PyResult<(f64, (f64, f64), u64)> {
+    if samples < 2 {
+        return Err(PyValueError::new_err(format!(
+            "samples must be at least 2, got {}",
+            samples
+        )));
+    }
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let estimate = py.allow_threads(|| graph.estimate_maximal_cliques(samples, seed));
+    Ok((
+        estimate.estimate,
+        (estimate.lower, estimate.upper),
+        estimate.samples,
+    ))
+}
+
+/// Builds the clique complex level by level, returning an `EnumerationResult` where
+/// `counts[i]` is the number of cliques with `i + 1` vertices (the f-vector of the
+/// clique complex) and `timings_ns[i]` the time spent building them. Each clique counts
+/// as a search node, its extensions as its candidates. Levels are streamed in chunks and
+/// dropped once counted. Ctrl-C raises `KeyboardInterrupt`.
+#[pyfunction]
+fn cliques_up_to(py: Python<'_>, adjacency_matrix: Vec<Vec<usize>>) -> PyResult<EnumerationResult> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
     let start_time = Instant::now();
-    while cliques.len() > 0 {
-        count += cliques.len();
-        cliques = graph.get_next_cliques(&cliques);
+    interruptible(py, |cancel| {
+        let mut timings = Vec::new();
+        let mut counts = Vec::new();
+        let mut stats = SearchStats::default();
+        let _ = levels::visit_levels(
+            &graph,
+            levels::LEVEL_CHUNK,
+            &mut stats,
+            &mut |chunk, elapsed| {
+                let size = chunk.size();
+                if counts.len() < size {
+                    counts.resize(size, 0);
+                    timings.resize(size, Duration::ZERO);
+                }
+                counts[size - 1] += chunk.len() as u64;
+                timings[size - 1] += elapsed;
+                match cancel.load(Ordering::Relaxed) {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                }
+            },
+        );
+        let outcome = Outcome {
+            results: counts.iter().sum(),
+            stopped: None,
+            stats,
+        };
+        let mut result = EnumerationResult::new(counts, start_time.elapsed(), outcome);
+        result.timings_ns = timings
+            .iter()
+            .map(|timing| timing.as_nanos() as u64)
+            .collect();
+        result
+    })
+}
+
+/// Histogram of maximal clique sizes: entry `i` is the number of maximal cliques with
+/// `i + 1` vertices. Accepts the same options as `find_cliques`.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, algorithm = "pivot", pivot = None, seed = 0, threads = None))]
+fn maximal_clique_sizes(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    algorithm: &str,
+    pivot: Option<&str>,
+    seed: u64,
+    threads: Option<usize>,
+) -> PyResult<Vec<u64>> {
+    let algorithm = Algorithm::parse(algorithm)?;
+    let pivot = parse_pivot(pivot, seed)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let (histogram, _) = interruptible(py, |cancel| {
+        algorithm.fold(
+            &graph,
+            pivot,
+            threads,
+            &Limits::default(),
+            cancel,
+            |histogram: &mut Vec<u64>, clique| {
+                bitmatrix::add_to_histogram(histogram, clique.len());
+                ControlFlow::Continue(())
+            },
+            bitmatrix::merge_histograms,
+        )
+    })?;
+    Ok(histogram)
+}
+
+/// Vertex–maximal-clique incidence. Returns `(cliques, memberships)`: the sorted
+/// maximal cliques and, for every vertex, the ids (indices into `cliques`) of the cliques
+/// containing it.
+#[pyfunction]
+#[allow(clippy::type_complexity)]
+fn vertex_clique_incidence(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+) -> PyResult<(Vec<Vec<usize>>, Vec<Vec<usize>>)> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| {
+        let (cliques, incidence) = graph.vertex_clique_incidence();
+        let memberships = (0..n_nodes).map(|v| incidence.cliques_of(v)).collect();
+        (cliques, memberships)
+    }))
+}
+
+/// Clique graph of the maximal cliques. Returns `(cliques, adjacency, overlaps)`:
+/// `adjacency[i]` lists the cliques sharing at least `min_overlap` vertices with clique
+/// `i`, and `overlaps` lists each such pair once as `(i, j, shared)`.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, min_overlap = 1))]
+#[allow(clippy::type_complexity)]
+fn clique_graph(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    min_overlap: usize,
+) -> PyResult<(Vec<Vec<usize>>, Vec<Vec<usize>>, Vec<(usize, usize, usize)>)> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| {
+        let clique_graph = graph.clique_graph(min_overlap);
+        let adjacency = (0..clique_graph.cliques.len())
+            .map(|i| clique_graph.graph.get_neighbours(i))
+            .collect();
+        (clique_graph.cliques, adjacency, clique_graph.overlaps)
+    }))
+}
+
+/// Greedy colouring with one of the strategies "largest_first", "smallest_last" or
+/// "dsatur". Returns the colour of every vertex, numbered from 0.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, strategy = "dsatur"))]
+fn greedy_colouring(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    strategy: &str,
+) -> PyResult<Vec<usize>> {
+    let order = match strategy {
+        "largest_first" => Some(ColourOrder::LargestFirst),
+        "smallest_last" => Some(ColourOrder::SmallestLast),
+        "dsatur" => None,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown strategy '{}', expected 'largest_first', 'smallest_last' or 'dsatur'",
+                strategy
+            )))
+        }
+    };
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let colouring = py.allow_threads(|| match order {
+        Some(order) => graph.greedy_colouring(order),
+        None => graph.dsatur_colouring(),
+    });
+    Ok(colouring.colours)
+}
+
+/// Returns `(chromatic_number, colours, optimal)` for a minimum colouring. `time_limit`
+/// behaves as in `maximum_clique`.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, time_limit = None))]
+fn chromatic_number(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    time_limit: Option<f64>,
+) -> PyResult<(usize, Vec<usize>, bool)> {
+    let time_limit = parse_time_limit(time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let colouring = py.allow_threads(|| graph.chromatic_number(time_limit));
+    Ok((colouring.n_colours, colouring.colours, colouring.optimal))
+}
+
+/// Returns `(cliques, optimal)` for a minimum clique cover: the vertices partitioned into
+/// as few cliques as possible. `time_limit` behaves as in `maximum_clique`.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, time_limit = None))]
+fn clique_cover(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    time_limit: Option<f64>,
+) -> PyResult<(Vec<Vec<usize>>, bool)> {
+    let time_limit = parse_time_limit(time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let cover = py.allow_threads(|| graph.clique_cover(time_limit));
+    Ok((cover.classes(), cover.optimal))
+}
+
+/// Lists the maximal independent sets, each one sorted. Ctrl-C raises
+/// `KeyboardInterrupt`.
+#[pyfunction]
+fn maximal_independent_sets(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+) -> PyResult<Vec<Vec<usize>>> {
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    interruptible(py, |cancel| {
+        let mut sets = Vec::new();
+        let _ = graph.visit_independent_sets(&mut |set: &[usize]| {
+            let mut set = set.to_vec();
+            set.sort_unstable();
+            sets.push(set);
+            match cancel.load(Ordering::Relaxed) {
+                true => ControlFlow::Break(()),
+                false => ControlFlow::Continue(()),
+            }
+        });
+        sets
+    })
+}
+
+/// Returns `(size, set, optimal)` for a maximum independent set. `time_limit` behaves as
+/// in `maximum_clique`.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, time_limit = None))]
+fn maximum_independent_set(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    time_limit: Option<f64>,
+) -> PyResult<(usize, Vec<usize>, bool)> {
+    let time_limit = parse_time_limit(time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let best = py.allow_threads(|| graph.maximum_independent_set(time_limit));
+    Ok((best.set.len(), best.set, best.optimal))
+}
+
+/// Lists the maximal k-plexes as `(plexes, stopped)`: sets in which each vertex is
+/// adjacent to all but at most `k` of the set, itself included (1-plexes are cliques).
+/// The limits and truncation report are those of `maximal_cliques`.
+#[pyfunction]
+#[pyo3(signature = (
+    adjacency_matrix,
+    k,
+    min_size = 1,
+    max_size = None,
+    max_results = None,
+    time_limit = None
+))]
+fn maximal_k_plexes(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k: usize,
+    min_size: usize,
+    max_size: Option<usize>,
+    max_results: Option<u64>,
+    time_limit: Option<f64>,
+) -> PyResult<(Vec<Vec<usize>>, Option<&'static str>)> {
+    if k < 1 {
+        return Err(PyValueError::new_err("k-plexes need k >= 1"));
+    }
+    let limits = parse_limits(max_results, min_size, max_size, time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    interruptible(py, |cancel| {
+        let mut plexes = Vec::new();
+        let outcome = graph.visit_k_plexes_limited(
+            k,
+            &limits,
+            &mut || cancel.load(Ordering::Relaxed),
+            &mut |plex: &[usize]| {
+                plexes.push(plex.to_vec());
+                ControlFlow::Continue(())
+            },
+        );
+        (plexes, stop_name(outcome.stopped))
+    })
+}
+
+/// Lists the maximal γ-quasi-cliques with at least `min_size` (and at most `max_size`)
+/// vertices as `(sets, stopped)`: sets in which every vertex is adjacent to at least
+/// `gamma * (size - 1)` of the others. Maximality is only known once the search ends, so
+/// `max_size` and `max_results` filter and truncate the sorted list, and a search stopped
+/// by `time_limit` or Ctrl-C returns the sets maximal among those found so far.
+#[pyfunction]
+#[pyo3(signature = (
+    adjacency_matrix,
+    gamma,
+    min_size = 2,
+    max_size = None,
+    max_results = None,
+    time_limit = None
+))]
+fn maximal_quasi_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    gamma: f64,
+    min_size: usize,
+    max_size: Option<usize>,
+    max_results: Option<u64>,
+    time_limit: Option<f64>,
+) -> PyResult<(Vec<Vec<usize>>, Option<&'static str>)> {
+    if !(gamma > 0.0 && gamma <= 1.0) {
+        return Err(PyValueError::new_err(format!(
+            "gamma must lie in (0, 1], got {}",
+            gamma
+        )));
+    }
+    let limits = parse_limits(max_results, min_size, max_size, time_limit)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    interruptible(py, |cancel| {
+        let (sets, outcome) =
+            graph.quasi_cliques_limited(gamma, &limits, &mut || cancel.load(Ordering::Relaxed));
+        (sets, stop_name(outcome.stopped))
+    })
+}
+
+/// Builds a bipartite graph from either a biadjacency matrix (`format="biadjacency"`, a
+/// row of 0/1 entries per left vertex) or a list of `(left, right)` pairs
+/// (`format="edges"`). `shape = (n_left, n_right)` sizes an edge list; by default each
+/// side ends at its largest vertex.
+fn parse_bipartite(
+    graph: Vec<Vec<usize>>,
+    format: &str,
+    shape: Option<(usize, usize)>,
+) -> PyResult<BipartiteMatrix> {
+    match format {
+        "biadjacency" => {
+            let n_right = graph.first().map_or(0, |row| row.len());
+            if graph.iter().any(|row| row.len() != n_right) {
+                return Err(PyValueError::new_err("biadjacency rows differ in length"));
+            }
+            Ok(BipartiteMatrix::from_biadjacency(&graph))
+        }
+        "edges" => {
+            let mut edges = Vec::with_capacity(graph.len());
+            for edge in &graph {
+                match edge.as_slice() {
+                    &[u, v] => edges.push((u, v)),
+                    _ => return Err(PyValueError::new_err("edges must be (left, right) pairs")),
+                }
+            }
+            let (n_left, n_right) = shape.unwrap_or_else(|| {
+                edges
+                    .iter()
+                    .fold((0, 0), |(l, r), &(u, v)| (l.max(u + 1), r.max(v + 1)))
+            });
+            if let Some(&(u, v)) = edges.iter().find(|&&(u, v)| u >= n_left || v >= n_right) {
+                return Err(PyValueError::new_err(format!(
+                    "edge ({}, {}) out of range for shape ({}, {})",
+                    u, v, n_left, n_right
+                )));
+            }
+            Ok(BipartiteMatrix::from_edges(&edges, n_left, n_right))
+        }
+        _ => Err(PyValueError::new_err(format!(
+            "unknown format '{}', expected 'biadjacency' or 'edges'",
+            format
+        ))),
+    }
+}
+
+/// Lists the maximal bicliques of a bipartite graph as `(left, right)` pairs of sorted
+/// vertex lists, keeping those with at least `min_left` left and `min_right` right
+/// vertices. See `parse_bipartite` for `format` and `shape`. Ctrl-C raises
+/// `KeyboardInterrupt`.
+#[pyfunction]
+#[pyo3(signature = (graph, min_left = 1, min_right = 1, format = "biadjacency", shape = None))]
+fn maximal_bicliques(
+    py: Python<'_>,
+    graph: Vec<Vec<usize>>,
+    min_left: usize,
+    min_right: usize,
+    format: &str,
+    shape: Option<(usize, usize)>,
+) -> PyResult<Vec<(Vec<usize>, Vec<usize>)>> {
+    let graph = parse_bipartite(graph, format, shape)?;
+    interruptible(py, |cancel| {
+        let mut bicliques = Vec::new();
+        let _ = graph.visit_bicliques(
+            min_left,
+            min_right,
+            &mut |left: &[usize], right: &[usize]| {
+                bicliques.push((left.to_vec(), right.to_vec()));
+                match cancel.load(Ordering::Relaxed) {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                }
+            },
+        );
+        bicliques
+    })
+}
+
+/// Counts the maximal bicliques `maximal_bicliques` would list.
+#[pyfunction]
+#[pyo3(signature = (graph, min_left = 1, min_right = 1, format = "biadjacency", shape = None))]
+fn count_bicliques(
+    py: Python<'_>,
+    graph: Vec<Vec<usize>>,
+    min_left: usize,
+    min_right: usize,
+    format: &str,
+    shape: Option<(usize, usize)>,
+) -> PyResult<u64> {
+    let graph = parse_bipartite(graph, format, shape)?;
+    interruptible(py, |cancel| {
+        let mut count = 0;
+        let _ = graph.visit_bicliques(min_left, min_right, &mut |_: &[usize], _: &[usize]| {
+            count += 1;
+            match cancel.load(Ordering::Relaxed) {
+                true => ControlFlow::Break(()),
+                false => ControlFlow::Continue(()),
+            }
+        });
+        count
+    })
+}
+
+/// Calls `callback(left, right)` for every maximal biclique `maximal_bicliques` would
+/// list; returning `False` stops the enumeration. Returns whether it ran to completion.
+#[pyfunction]
+#[pyo3(signature = (graph, callback, min_left = 1, min_right = 1, format = "biadjacency", shape = None))]
+fn visit_bicliques(
+    py: Python<'_>,
+    graph: Vec<Vec<usize>>,
+    callback: PyObject,
+    min_left: usize,
+    min_right: usize,
+    format: &str,
+    shape: Option<(usize, usize)>,
+) -> PyResult<bool> {
+    let graph = parse_bipartite(graph, format, shape)?;
+    let callback = PyCallback {
+        callback,
+        error: Mutex::new(None),
+    };
+    let flow = interruptible(py, |cancel| {
+        graph.visit_bicliques(
+            min_left,
+            min_right,
+            &mut |left: &[usize], right: &[usize]| {
+                if cancel.load(Ordering::Relaxed) {
+                    return ControlFlow::Break(());
+                }
+                callback.invoke((left.to_vec(), right.to_vec()))
+            },
+        )
+    })?;
+    match callback.error.into_inner().unwrap() {
+        Some(err) => Err(err),
+        None => Ok(flow.is_continue()),
+    }
+}
+
+/// File errors as Python exceptions: a corrupt file (or a checkpoint taken on another
+/// graph) is a `ValueError`, anything else the matching `OSError`.
+fn file_error(err: io::Error) -> PyErr {
+    match err.kind() {
+        io::ErrorKind::InvalidData => PyValueError::new_err(err.to_string()),
+        _ => err.into(),
     }
-    let elapsed = start_time.elapsed();
-    Ok((elapsed.as_secs() as f64, count as u32))
+}
+
+fn parse_interval(interval: Option<f64>) -> PyResult<Option<Duration>> {
+    interval
+        .map(|seconds| {
+            Duration::try_from_secs_f64(seconds)
+                .map_err(|_| PyValueError::new_err(format!("invalid interval {}", seconds)))
+        })
+        .transpose()
+}
+
+/// Counts the maximal cliques in a run that can be stopped and picked up again. Progress
+/// is saved to the `checkpoint` file every `interval` seconds, when `callback(clique)`
+/// returns `False`, on Ctrl-C (which then raises `KeyboardInterrupt`) and at the end.
+/// Calling again with the same file carries on from the last save; cliques found after
+/// it are reported again. Returns `(count, finished)`, the count including earlier runs.
+/// `pivot` and `seed` only apply when the file does not exist yet; a checkpoint taken
+/// on another graph raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (
+    adjacency_matrix,
+    checkpoint,
+    callback = None,
+    interval = 600.0,
+    pivot = None,
+    seed = 0
+))]
+fn resume_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    checkpoint: PathBuf,
+    callback: Option<PyObject>,
+    interval: Option<f64>,
+    pivot: Option<&str>,
+    seed: u64,
+) -> PyResult<(u64, bool)> {
+    let pivot = parse_pivot(pivot, seed)?.unwrap_or_default();
+    let interval = parse_interval(interval)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let mut state = match checkpoint.exists() {
+        true => CliqueCheckpoint::load(&graph, &checkpoint).map_err(file_error)?,
+        false => CliqueCheckpoint::new(&graph, CliqueSearch::new(&graph, pivot)),
+    };
+    let callback = callback.map(|callback| PyCallback {
+        callback,
+        error: Mutex::new(None),
+    });
+    let result = interruptible(py, |cancel| {
+        let mut policy = CheckpointPolicy {
+            path: checkpoint,
+            interval,
+            request: None,
+            interrupt: &mut || cancel.load(Ordering::Relaxed),
+        };
+        graph.resume_cliques(
+            &mut state,
+            &mut policy,
+            &mut |clique: &[usize]| match &callback {
+                Some(callback) => callback.call(clique),
+                None => ControlFlow::Continue(()),
+            },
+        )
+    })?;
+    if let Some(err) = callback.and_then(|callback| callback.error.into_inner().unwrap()) {
+        return Err(err);
+    }
+    let _ = result.map_err(file_error)?;
+    Ok((state.found, state.is_finished()))
+}
+
+/// Counts the cliques with exactly `k` vertices, or visits them if `callback` is given,
+/// in a run saved to and resumed from `checkpoint` as in `resume_cliques`. Progress is
+/// kept per vertex, so a run stopped part-way through one redoes it when resumed, and
+/// without a callback stops are only noticed between vertices.
+#[pyfunction]
+#[pyo3(signature = (adjacency_matrix, k, checkpoint, callback = None, interval = 600.0))]
+fn resume_k_cliques(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k: usize,
+    checkpoint: PathBuf,
+    callback: Option<PyObject>,
+    interval: Option<f64>,
+) -> PyResult<(u64, bool)> {
+    let interval = parse_interval(interval)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    let mut state = match checkpoint.exists() {
+        true => KCliqueCheckpoint::load(&graph, &checkpoint).map_err(file_error)?,
+        false => KCliqueCheckpoint::new(&graph, k),
+    };
+    if state.k != k {
+        return Err(PyValueError::new_err(format!(
+            "checkpoint is for k = {}, not {}",
+            state.k, k
+        )));
+    }
+    let callback = callback.map(|callback| PyCallback {
+        callback,
+        error: Mutex::new(None),
+    });
+    let result = interruptible(py, |cancel| {
+        let mut policy = CheckpointPolicy {
+            path: checkpoint,
+            interval,
+            request: None,
+            interrupt: &mut || cancel.load(Ordering::Relaxed),
+        };
+        match &callback {
+            Some(callback) => {
+                graph.resume_k_cliques(&mut state, &mut policy, &mut |clique: &[usize]| {
+                    callback.call(clique)
+                })
+            }
+            None => graph.resume_count_k_cliques(&mut state, &mut policy),
+        }
+    })?;
+    if let Some(err) = callback.and_then(|callback| callback.error.into_inner().unwrap()) {
+        return Err(err);
+    }
+    let _ = result.map_err(file_error)?;
+    Ok((state.found, state.is_finished(&graph)))
+}
+
+fn check_k(k_min: usize) -> PyResult<()> {
+    if k_min < 2 {
+        return Err(PyValueError::new_err(format!(
+            "k-clique communities need k >= 2, got {}",
+            k_min
+        )));
+    }
+    Ok(())
+}
+
+/// k-clique communities (clique percolation): unions of k-cliques chained through
+/// shared (k - 1)-cliques. Returns the communities as sorted vertex lists; they may
+/// overlap.
+#[pyfunction]
+fn k_clique_communities(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_k(k)?;
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.k_clique_communities(k)))
+}
+
+/// k-clique communities for every k from `k_min` to `k_max`, as a dict keyed by k,
+/// from a single maximal clique enumeration.
+#[pyfunction]
+fn k_clique_communities_range(
+    py: Python<'_>,
+    adjacency_matrix: Vec<Vec<usize>>,
+    k_min: usize,
+    k_max: usize,
+) -> PyResult<BTreeMap<usize, Vec<Vec<usize>>>> {
+    check_k(k_min)?;
+    if k_max < k_min {
+        return Err(PyValueError::new_err(format!(
+            "k_max must be at least k_min, got k_min = {} and k_max = {}",
+            k_min, k_max
+        )));
+    }
+    let n_nodes = adjacency_matrix.len();
+    let graph = Bitmatrix::new(adjacency_matrix, n_nodes);
+    Ok(py.allow_threads(|| graph.k_clique_communities_range(k_min..=k_max)))
 }
 
 /// A Python module implemented in Rust.
 #[pymodule]
 fn heron(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(find_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(maximal_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(visit_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(write_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(read_cliques, m)?)?;
+    m.add_class::<CliqueReader>()?;
+    m.add_function(wrap_pyfunction!(cliques_containing, m)?)?;
+    m.add_function(wrap_pyfunction!(cliques_with_vertex, m)?)?;
+    m.add_function(wrap_pyfunction!(cliques_with_edge, m)?)?;
+    m.add_function(wrap_pyfunction!(degeneracy_ordering, m)?)?;
+    m.add_function(wrap_pyfunction!(maximum_clique, m)?)?;
+    m.add_function(wrap_pyfunction!(maximum_weight_clique, m)?)?;
+    m.add_function(wrap_pyfunction!(k_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(count_k_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(count_cliques_up_to, m)?)?;
+    m.add_function(wrap_pyfunction!(resume_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(resume_k_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(estimate_k_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(estimate_maximal_cliques, m)?)?;
+    m.add_class::<CliqueIterator>()?;
+    m.add_class::<EnumerationResult>()?;
     m.add_function(wrap_pyfunction!(cliques_up_to, m)?)?;
+    m.add_function(wrap_pyfunction!(maximal_clique_sizes, m)?)?;
+    m.add_function(wrap_pyfunction!(maximal_independent_sets, m)?)?;
+    m.add_function(wrap_pyfunction!(maximum_independent_set, m)?)?;
+    m.add_function(wrap_pyfunction!(maximal_k_plexes, m)?)?;
+    m.add_function(wrap_pyfunction!(maximal_quasi_cliques, m)?)?;
+    m.add_function(wrap_pyfunction!(maximal_bicliques, m)?)?;
+    m.add_function(wrap_pyfunction!(count_bicliques, m)?)?;
+    m.add_function(wrap_pyfunction!(visit_bicliques, m)?)?;
+    m.add_function(wrap_pyfunction!(greedy_colouring, m)?)?;
+    m.add_function(wrap_pyfunction!(chromatic_number, m)?)?;
+    m.add_function(wrap_pyfunction!(clique_cover, m)?)?;
+    m.add_function(wrap_pyfunction!(vertex_clique_incidence, m)?)?;
+    m.add_function(wrap_pyfunction!(clique_graph, m)?)?;
+    m.add_function(wrap_pyfunction!(k_clique_communities, m)?)?;
+    m.add_function(wrap_pyfunction!(k_clique_communities_range, m)?)?;
     m.add_function(wrap_pyfunction!(betti_numbers, m)?)?;
+    m.add_function(wrap_pyfunction!(betti_numbers_limited, m)?)?;
+    m.add_function(wrap_pyfunction!(integral_homology, m)?)?;
     Ok(())
 }
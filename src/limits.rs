@@ -0,0 +1,400 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::degeneracy::Degeneracy;
+use crate::search::{CliqueSearch, PivotStrategy, SearchStats, Step};
+use crate::visitor::CliqueVisitor;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+/// Search nodes visited between two checks of the clock and of the interrupt hook.
+pub const CHECK_INTERVAL: usize = 1024;
+
+/// Bounds on how much work an enumeration may do. The default imposes none.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    /// Stop once this many cliques have been reported.
+    pub max_results: Option<u64>,
+    /// Only report cliques with at least this many vertices.
+    pub min_size: usize,
+    /// Only report cliques with at most this many vertices.
+    pub max_size: Option<usize>,
+    /// Wall-clock budget for the whole run.
+    pub time_budget: Option<Duration>,
+    /// Highest simplex dimension considered by homology computations.
+    pub max_dimension: Option<usize>,
+}
+
+impl Limits {
+    /// When the time budget runs out, counting from now.
+    pub fn deadline(&self) -> Option<Instant> {
+        deadline_after(self.time_budget)
+    }
+}
+
+/// The instant `budget` from now. A budget reaching past what the clock can represent
+/// sets no deadline.
+fn deadline_after(budget: Option<Duration>) -> Option<Instant> {
+    budget.and_then(|budget| Instant::now().checked_add(budget))
+}
+
+/// Time limit of the branch and bound solvers, which count the nodes they expand and
+/// only look at the clock every `CHECK_INTERVAL` of them.
+pub struct Timer {
+    deadline: Option<Instant>,
+    nodes: usize,
+    expired: bool,
+}
+
+impl Timer {
+    pub fn new(time_limit: Option<Duration>) -> Self {
+        Timer {
+            deadline: deadline_after(time_limit),
+            nodes: 0,
+            expired: false,
+        }
+    }
+
+    /// Counts one more node and tells whether the time is up. Once up, it stays up.
+    pub fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.expired && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.expired = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.expired
+    }
+
+    pub fn expired(&self) -> bool {
+        self.expired
+    }
+}
+
+/// Why a run ended before covering everything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    MaxResults,
+    TimeBudget,
+    MaxDimension,
+    /// The interrupt hook asked to stop (e.g. Ctrl-C in Python).
+    Interrupted,
+    /// The visitor asked to stop.
+    Visitor,
+}
+
+impl StopReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopReason::MaxResults => "max_results",
+            StopReason::TimeBudget => "time_budget",
+            StopReason::MaxDimension => "max_dimension",
+            StopReason::Interrupted => "interrupted",
+            StopReason::Visitor => "visitor",
+        }
+    }
+}
+
+/// Result of a limited run: how many cliques were reported, if the run was truncated,
+/// why, and the shape of the search tree it explored.
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome {
+    pub results: u64,
+    pub stopped: Option<StopReason>,
+    pub stats: SearchStats,
+}
+
+/// Runtime bookkeeping for `Limits`: counts results, watches the deadline and polls the
+/// interrupt hook, remembering the first reason to stop.
+pub struct Budget<'a> {
+    max_results: Option<u64>,
+    deadline: Option<Instant>,
+    interrupt: &'a mut dyn FnMut() -> bool,
+    pub results: u64,
+    pub stopped: Option<StopReason>,
+    pub stats: SearchStats,
+}
+
+impl<'a> Budget<'a> {
+    pub fn new(limits: &Limits, interrupt: &'a mut dyn FnMut() -> bool) -> Self {
+        Budget {
+            max_results: limits.max_results,
+            deadline: limits.deadline(),
+            interrupt,
+            results: 0,
+            stopped: None,
+            stats: SearchStats::default(),
+        }
+    }
+
+    pub fn stop(&mut self, reason: StopReason) -> ControlFlow<()> {
+        self.stopped.get_or_insert(reason);
+        ControlFlow::Break(())
+    }
+
+    /// Checks the clock and the interrupt hook.
+    pub fn check(&mut self) -> ControlFlow<()> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return self.stop(StopReason::TimeBudget);
+        }
+        if (self.interrupt)() {
+            return self.stop(StopReason::Interrupted);
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Hands a clique to the visitor and counts it, unless `max_results` are already in.
+    pub fn report<V: CliqueVisitor>(
+        &mut self,
+        visitor: &mut V,
+        clique: &[usize],
+    ) -> ControlFlow<()> {
+        if self.max_results.is_some_and(|max| self.results >= max) {
+            return self.stop(StopReason::MaxResults);
+        }
+        if visitor.visit(clique).is_break() {
+            return self.stop(StopReason::Visitor);
+        }
+        self.results += 1;
+        if self.max_results.is_some_and(|max| self.results >= max) {
+            return self.stop(StopReason::MaxResults);
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        Outcome {
+            results: self.results,
+            stopped: self.stopped,
+            stats: self.stats,
+        }
+    }
+
+    /// Runs `search` to exhaustion (or until a limit hits), checking the clock on entry and
+    /// every `CHECK_INTERVAL` nodes after that, and reporting every clique found
+    /// behind `prefix` with its local ids translated through `labels` when given. The
+    /// search's statistics are added to `stats`.
+    pub fn drive<V: CliqueVisitor>(
+        &mut self,
+        graph: &Bitmatrix,
+        mut search: CliqueSearch,
+        prefix: &[usize],
+        labels: Option<&[usize]>,
+        visitor: &mut V,
+    ) -> ControlFlow<()> {
+        self.check()?;
+        let mut clique = prefix.to_vec();
+        let flow = loop {
+            let flow = match search.step(graph, CHECK_INTERVAL) {
+                Step::Clique(found) => {
+                    clique.truncate(prefix.len());
+                    match labels {
+                        Some(labels) => clique.extend(found.iter().map(|&i| labels[i])),
+                        None => clique.extend_from_slice(found),
+                    }
+                    self.report(visitor, &clique)
+                }
+                Step::Paused => self.check(),
+                Step::Exhausted => break ControlFlow::Continue(()),
+            };
+            if flow.is_break() {
+                break flow;
+            }
+        };
+        self.stats.merge(&search.stats(), prefix.len());
+        flow
+    }
+}
+
+/// Maximal clique enumeration under `Limits`. `interrupt` is polled every
+/// `CHECK_INTERVAL` search nodes and stops the run when it returns true.
+pub trait LimitedCliques {
+    fn visit_cliques_limited<V: CliqueVisitor>(
+        &self,
+        sparse: bool,
+        pivot: PivotStrategy,
+        limits: &Limits,
+        interrupt: &mut dyn FnMut() -> bool,
+        visitor: &mut V,
+    ) -> Outcome;
+}
+
+impl LimitedCliques for Bitmatrix {
+    fn visit_cliques_limited<V: CliqueVisitor>(
+        &self,
+        sparse: bool,
+        pivot: PivotStrategy,
+        limits: &Limits,
+        interrupt: &mut dyn FnMut() -> bool,
+        visitor: &mut V,
+    ) -> Outcome {
+        let mut budget = Budget::new(limits, interrupt);
+        if !sparse {
+            let search =
+                CliqueSearch::new(self, pivot).with_size_bounds(limits.min_size, limits.max_size);
+            let _ = budget.drive(self, search, &[], None, visitor);
+            return budget.outcome();
+        }
+        // each local search runs behind its vertex, so its size bounds are one smaller
+        let local_min = limits.min_size.saturating_sub(1);
+        let local_max = limits.max_size.map(|max| max.saturating_sub(1));
+        let ordering = self.degeneracy_ordering();
+        let _ = ordering.order.iter().try_for_each(|&v| {
+            let local = self.local_neighbourhood(v, &ordering.position);
+            budget.stats.record_node(1, local.later.len());
+            if local.is_isolated() {
+                budget.check()?;
+                if limits.min_size <= 1 && local_max != Some(0) {
+                    budget.report(visitor, &[v])?;
+                }
+                return ControlFlow::Continue(());
+            }
+            if local_max == Some(0) {
+                return ControlFlow::Continue(());
+            }
+            let search = local.search(pivot).with_size_bounds(local_min, local_max);
+            budget.drive(&local.graph, search, &[v], Some(&local.later), visitor)
+        });
+        budget.outcome()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel::ParallelCliques;
+    use crate::testutil::{maximal_cliques, pseudo_random_graph};
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn test_limits() {
+        let n_nodes = 30;
+        let graph = pseudo_random_graph(n_nodes, 7, 4, 2);
+        let all = maximal_cliques(&graph);
+        for sparse in [false, true] {
+            let limits = Limits {
+                min_size: 3,
+                max_size: Some(4),
+                ..Limits::default()
+            };
+            let mut found = Vec::new();
+            let outcome = graph.visit_cliques_limited(
+                sparse,
+                PivotStrategy::Tomita,
+                &limits,
+                &mut || false,
+                &mut |clique: &[usize]| {
+                    let mut clique = clique.to_vec();
+                    clique.sort_unstable();
+                    found.push(clique);
+                    ControlFlow::Continue(())
+                },
+            );
+            let mut expected: Vec<Vec<usize>> = all
+                .iter()
+                .filter(|c| c.len() >= 3 && c.len() <= 4)
+                .cloned()
+                .collect();
+            expected.sort();
+            found.sort();
+            assert_eq!(found, expected);
+            assert_eq!(outcome.stopped, None);
+
+            let limits = Limits {
+                max_results: Some(5),
+                ..Limits::default()
+            };
+            let outcome = graph.visit_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                &limits,
+                &mut || false,
+                &mut |_: &[usize]| ControlFlow::Continue(()),
+            );
+            assert_eq!(outcome.results, 5);
+            assert_eq!(outcome.stopped, Some(StopReason::MaxResults));
+
+            let outcome = graph.visit_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                &Limits::default(),
+                &mut || true,
+                &mut |_: &[usize]| ControlFlow::Continue(()),
+            );
+            assert_eq!(outcome.stopped, Some(StopReason::Interrupted));
+
+            // no room for even one clique, sequentially or in parallel
+            let limits = Limits {
+                max_results: Some(0),
+                ..Limits::default()
+            };
+            let mut visited = 0;
+            let outcome = graph.visit_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                &limits,
+                &mut || false,
+                &mut |_: &[usize]| {
+                    visited += 1;
+                    ControlFlow::Continue(())
+                },
+            );
+            let (folded, par_outcome) = graph.par_fold_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                2,
+                &limits,
+                &AtomicBool::new(false),
+                |count: &mut u64, _: &[usize]| {
+                    *count += 1;
+                    ControlFlow::Continue(())
+                },
+                |a, b| a + b,
+            );
+            assert_eq!((visited, outcome.results), (0, 0));
+            assert_eq!((folded, par_outcome.results), (0, 0));
+            assert_eq!(outcome.stopped, Some(StopReason::MaxResults));
+            assert_eq!(par_outcome.stopped, outcome.stopped);
+
+            // a budget the clock cannot reach is no budget at all
+            let limits = Limits {
+                time_budget: Some(Duration::MAX),
+                ..Limits::default()
+            };
+            let outcome = graph.visit_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                &limits,
+                &mut || false,
+                &mut |_: &[usize]| ControlFlow::Continue(()),
+            );
+            let (_, par_outcome) = graph.par_fold_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                2,
+                &limits,
+                &AtomicBool::new(false),
+                |_: &mut (), _: &[usize]| ControlFlow::Continue(()),
+                |_, _| (),
+            );
+            assert_eq!((outcome.results, outcome.stopped), (all.len() as u64, None));
+            assert_eq!(
+                (par_outcome.results, par_outcome.stopped),
+                (all.len() as u64, None)
+            );
+        }
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut timer = Timer::new(Some(Duration::ZERO));
+        assert!((1..CHECK_INTERVAL).all(|_| !timer.tick()));
+        assert!(timer.tick() && timer.tick() && timer.expired());
+        for time_limit in [None, Some(Duration::MAX)] {
+            let mut timer = Timer::new(time_limit);
+            assert!((0..4 * CHECK_INTERVAL).all(|_| !timer.tick()));
+            assert!(!timer.expired());
+        }
+    }
+}
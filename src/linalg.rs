@@ -1,53 +1,485 @@
-use crate::bitvec::Bitvec;
+use hashbrown::{HashMap, HashSet};
 
-fn xor_rows(dest: &mut Bitvec, src: &Bitvec, col: usize) {
-    let lower_triag = col / 8;
-    for (dest_byte, src_byte) in dest
-        .iter_mut()
-        .skip(lower_triag)
-        .zip(src.iter().skip(lower_triag))
-    {
-        *dest_byte ^= *src_byte;
+/// Symmetric difference of two sorted index lists: the sum of two sparse Z/2 vectors.
+fn xor_sorted(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut sum = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => {
+                sum.push(a[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                sum.push(b[j]);
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
     }
+    sum.extend_from_slice(&a[i..]);
+    sum.extend_from_slice(&b[j..]);
+    sum
 }
 
-pub fn gaussian_elimination(matrix: &mut Vec<Bitvec>, num_cols: usize) {
-    let num_rows = matrix.len();
+/// Rank of a sparse Z/2 matrix fed one column at a time, each column given as the
+/// sorted row indices of its ones. Every column is reduced against the earlier ones on
+/// its lowest one as it arrives, so only the reduced independent columns are kept and
+/// the matrix itself never has to be stored.
+#[derive(Default)]
+pub struct SparseRank {
+    // reduced columns, keyed by their lowest row
+    pivots: HashMap<usize, Vec<usize>>,
+}
 
-    let mut pivot_row = 0;
-    for col in 0..num_cols {
-        // Find all rows with ones in the pivot column
-        let mut found_rows = Vec::new();
-        for row in pivot_row..num_rows {
-            if !matrix[row].contains(col) {
-                continue;
+impl SparseRank {
+    pub fn new() -> Self {
+        SparseRank::default()
+    }
+
+    /// Adds a column; returns whether it was independent of the columns before it.
+    pub fn add_column(&mut self, mut column: Vec<usize>) -> bool {
+        while let Some(&low) = column.last() {
+            match self.pivots.get(&low) {
+                Some(pivot) => column = xor_sorted(&column, pivot),
+                None => {
+                    self.pivots.insert(low, column);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+}
+
+/// Coefficients for sparse elimination. Elements are represented by integers in
+/// `0..characteristic()`, so a field is just the arithmetic on those representatives.
+pub trait Field: Copy {
+    fn characteristic(&self) -> u64;
+
+    /// Image of an integer, e.g. the signs of an oriented boundary.
+    fn embed(&self, value: i64) -> u64;
+
+    fn add(&self, a: u64, b: u64) -> u64;
+
+    fn neg(&self, a: u64) -> u64;
+
+    fn mul(&self, a: u64, b: u64) -> u64;
+
+    /// Multiplicative inverse of a non-zero element.
+    fn inv(&self, a: u64) -> u64;
+}
+
+/// The prime field Z/p. Primes are kept below 2^32 so products fit in a u64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrimeField {
+    p: u64,
+}
+
+impl PrimeField {
+    /// Z/p, or `None` when `p` is not a prime below 2^32.
+    pub fn new(p: u64) -> Option<Self> {
+        if !(2..1 << 32).contains(&p) {
+            return None;
+        }
+        let is_prime = (2..)
+            .take_while(|d| d * d <= p)
+            .all(|d| !p.is_multiple_of(d));
+        is_prime.then_some(PrimeField { p })
+    }
+}
+
+impl Field for PrimeField {
+    fn characteristic(&self) -> u64 {
+        self.p
+    }
+
+    fn embed(&self, value: i64) -> u64 {
+        value.rem_euclid(self.p as i64) as u64
+    }
+
+    fn add(&self, a: u64, b: u64) -> u64 {
+        (a + b) % self.p
+    }
+
+    fn neg(&self, a: u64) -> u64 {
+        (self.p - a) % self.p
+    }
+
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        a * b % self.p
+    }
+
+    fn inv(&self, a: u64) -> u64 {
+        // Fermat: a^(p - 2) is the inverse of a modulo a prime p
+        let (mut base, mut exp, mut inverse) = (a, self.p - 2, 1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                inverse = self.mul(inverse, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        inverse
+    }
+}
+
+/// `a + factor * b` for sparse vectors given as `(row, coefficient)` pairs sorted by
+/// row, dropping the entries that cancel.
+fn axpy_sorted<F: Field>(
+    field: &F,
+    a: &[(usize, u64)],
+    factor: u64,
+    b: &[(usize, u64)],
+) -> Vec<(usize, u64)> {
+    let mut sum = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            std::cmp::Ordering::Less => {
+                sum.push(a[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                sum.push((b[j].0, field.mul(factor, b[j].1)));
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                let value = field.add(a[i].1, field.mul(factor, b[j].1));
+                if value != 0 {
+                    sum.push((a[i].0, value));
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    sum.extend_from_slice(&a[i..]);
+    sum.extend(
+        b[j..]
+            .iter()
+            .map(|&(row, value)| (row, field.mul(factor, value))),
+    );
+    sum
+}
+
+/// Rank of a sparse matrix over any [`Field`], fed one column at a time as sorted
+/// `(row, coefficient)` pairs with non-zero coefficients. Works like [`SparseRank`]:
+/// each kept column is scaled so its lowest entry is one, and later columns are
+/// reduced against it on that row.
+pub struct FieldRank<F: Field> {
+    field: F,
+    // reduced columns, keyed by their lowest row, whose entry there is one
+    pivots: HashMap<usize, Vec<(usize, u64)>>,
+}
+
+impl<F: Field> FieldRank<F> {
+    pub fn new(field: F) -> Self {
+        FieldRank {
+            field,
+            pivots: HashMap::new(),
+        }
+    }
+
+    pub fn field(&self) -> F {
+        self.field
+    }
+
+    /// Adds a column; returns whether it was independent of the columns before it.
+    pub fn add_column(&mut self, mut column: Vec<(usize, u64)>) -> bool {
+        while let Some(&(low, value)) = column.last() {
+            match self.pivots.get(&low) {
+                Some(pivot) => {
+                    column = axpy_sorted(&self.field, &column, self.field.neg(value), pivot)
+                }
+                None => {
+                    let scale = self.field.inv(value);
+                    for entry in column.iter_mut() {
+                        entry.1 = self.field.mul(scale, entry.1);
+                    }
+                    self.pivots.insert(low, column);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+}
+
+/// Rank and torsion coefficients of an integer matrix: the diagonal of its Smith normal
+/// form has `rank` non-zero entries, and `torsion` lists those greater than one in
+/// increasing order, each dividing the next.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SmithForm {
+    pub rank: usize,
+    pub torsion: Vec<u64>,
+}
+
+/// Raised when an entry outgrows the integers used for the elimination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CoefficientOverflow;
+
+impl std::fmt::Display for CoefficientOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "coefficient overflow in Smith normal form")
+    }
+}
+
+impl std::error::Error for CoefficientOverflow {}
+
+/// `a + factor * b` for sparse integer vectors sorted by row, like [`axpy_sorted`].
+fn axpy_sorted_int(
+    a: &[(usize, i64)],
+    factor: i64,
+    b: &[(usize, i64)],
+) -> Result<Vec<(usize, i64)>, CoefficientOverflow> {
+    let scaled = |value: i64| factor.checked_mul(value).ok_or(CoefficientOverflow);
+    let mut sum = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            std::cmp::Ordering::Less => {
+                sum.push(a[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                sum.push((b[j].0, scaled(b[j].1)?));
+                j += 1;
             }
-            found_rows.push(row);
+            std::cmp::Ordering::Equal => {
+                let value = a[i].1.checked_add(scaled(b[j].1)?);
+                match value.ok_or(CoefficientOverflow)? {
+                    0 => {}
+                    value => sum.push((a[i].0, value)),
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    sum.extend_from_slice(&a[i..]);
+    for &(row, value) in &b[j..] {
+        sum.push((row, scaled(value)?));
+    }
+    Ok(sum)
+}
+
+/// Smith normal form of a sparse integer matrix with `n_rows` rows, given as columns of
+/// `(row, entry)` pairs sorted by row with non-zero entries. Returns `None` if
+/// `interrupt` returns true, which it is asked between pivots.
+///
+/// Boundary matrices are mostly reduced by entries of ±1, which eliminate their row and
+/// column exactly, so those are taken first, sparsest row and column first to keep the
+/// fill-in down. Whatever is left has no unit entries and is usually tiny; it is reduced
+/// densely, always pivoting on the smallest entry so the Euclidean steps keep the
+/// coefficients small.
+pub fn smith_normal_form(
+    n_rows: usize,
+    mut columns: Vec<Vec<(usize, i64)>>,
+    interrupt: &mut dyn FnMut() -> bool,
+) -> Result<Option<SmithForm>, CoefficientOverflow> {
+    // the live columns with an entry in each row
+    let mut rows: Vec<HashSet<usize>> = vec![HashSet::new(); n_rows];
+    for (c, column) in columns.iter().enumerate() {
+        for &(r, _) in column {
+            rows[r].insert(c);
         }
-        match found_rows.get(0) {
-            Some(row) => {
-                matrix.swap(pivot_row, *row);
+    }
+    let mut alive: Vec<usize> = (0..columns.len()).collect();
+    let mut rank = 0;
+    loop {
+        alive.retain(|&c| !columns[c].is_empty());
+        alive.sort_by_key(|&c| columns[c].len());
+        let mut pivoted = false;
+        for &c in &alive {
+            if columns[c].is_empty() {
+                continue;
+            }
+            let unit = columns[c]
+                .iter()
+                .filter(|&&(_, value)| value.abs() == 1)
+                .min_by_key(|&&(r, _)| rows[r].len());
+            let Some(&(r, unit)) = unit else {
+                continue;
+            };
+            if interrupt() {
+                return Ok(None);
             }
-            None => continue,
+            // clear row r from the other columns; the pivot column is then cut off from
+            // the rest by row operations, which leave the other columns untouched
+            let pivot = std::mem::take(&mut columns[c]);
+            for &(row, _) in &pivot {
+                rows[row].remove(&c);
+            }
+            let others: Vec<usize> = rows[r].drain().collect();
+            for other in others {
+                let column = &columns[other];
+                let at = column.binary_search_by_key(&r, |&(row, _)| row).unwrap();
+                let factor = column[at].1.checked_mul(-unit).ok_or(CoefficientOverflow)?;
+                for &(row, _) in &pivot {
+                    rows[row].remove(&other);
+                }
+                let column = axpy_sorted_int(column, factor, &pivot)?;
+                for &(row, _) in &pivot {
+                    if column.binary_search_by_key(&row, |&(row, _)| row).is_ok() {
+                        rows[row].insert(other);
+                    }
+                }
+                columns[other] = column;
+            }
+            rank += 1;
+            pivoted = true;
         }
-        let pivot_row_slice = &matrix[pivot_row].clone();
-        for row in found_rows.into_iter().skip(1) {
-            xor_rows(&mut matrix[row], &pivot_row_slice, col);
+        if !pivoted {
+            break;
         }
-        pivot_row += 1;
     }
+    alive.retain(|&c| !columns[c].is_empty());
+    let mut live_rows: Vec<usize> = (0..n_rows).filter(|&r| !rows[r].is_empty()).collect();
+    live_rows.sort_unstable();
+    let mut dense = vec![vec![0i128; alive.len()]; live_rows.len()];
+    for (j, &c) in alive.iter().enumerate() {
+        for &(r, value) in &columns[c] {
+            let i = live_rows.binary_search(&r).unwrap();
+            dense[i][j] = value as i128;
+        }
+    }
+    let Some(divisors) = dense_smith_diagonal(dense, interrupt)? else {
+        return Ok(None);
+    };
+    let mut torsion = Vec::new();
+    for divisor in divisors {
+        rank += 1;
+        if divisor > 1 {
+            torsion.push(u64::try_from(divisor).map_err(|_| CoefficientOverflow)?);
+        }
+    }
+    Ok(Some(SmithForm { rank, torsion }))
+}
+
+/// `dest += factor * src`, entry by entry.
+fn add_row(dest: &mut [i128], factor: i128, src: &[i128]) -> Result<(), CoefficientOverflow> {
+    for (x, &y) in dest.iter_mut().zip(src) {
+        let step = factor.checked_mul(y).ok_or(CoefficientOverflow)?;
+        *x = x.checked_add(step).ok_or(CoefficientOverflow)?;
+    }
+    Ok(())
 }
 
-pub fn rank(matrix: &mut Vec<Bitvec>, num_cols: usize) -> usize {
-    let num_rows = matrix.len();
-    gaussian_elimination(matrix, num_cols);
-    let mut count = 0;
-    for row in matrix.iter().rev() {
-        if row.is_empty() {
-            count += 1;
-        } else {
-            return num_rows - count;
+/// Non-zero diagonal of the Smith normal form of a dense matrix, in increasing order.
+fn dense_smith_diagonal(
+    mut a: Vec<Vec<i128>>,
+    interrupt: &mut dyn FnMut() -> bool,
+) -> Result<Option<Vec<i128>>, CoefficientOverflow> {
+    let n_rows = a.len();
+    let n_columns = a.first().map_or(0, Vec::len);
+    let mut diagonal = Vec::new();
+    for t in 0..n_rows.min(n_columns) {
+        if interrupt() {
+            return Ok(None);
         }
+        loop {
+            // move the smallest entry left in the corner
+            let smallest = (t..n_rows)
+                .flat_map(|i| (t..n_columns).map(move |j| (i, j)))
+                .filter(|&(i, j)| a[i][j] != 0)
+                .min_by_key(|&(i, j)| a[i][j].unsigned_abs());
+            let Some((i, j)) = smallest else {
+                return Ok(Some(diagonal));
+            };
+            a.swap(t, i);
+            for row in a.iter_mut() {
+                row.swap(t, j);
+            }
+            let pivot = a[t][t];
+            let mut reduced = true;
+            for i in t + 1..n_rows {
+                let q = a[i][t] / pivot;
+                if q != 0 {
+                    let (above, below) = a.split_at_mut(i);
+                    add_row(&mut below[0][t..], -q, &above[t][t..])?;
+                }
+                reduced &= a[i][t] == 0;
+            }
+            for j in t + 1..n_columns {
+                let q = a[t][j] / pivot;
+                if q != 0 {
+                    for row in a.iter_mut().skip(t) {
+                        let step = q.checked_mul(row[t]).ok_or(CoefficientOverflow)?;
+                        row[j] = row[j].checked_sub(step).ok_or(CoefficientOverflow)?;
+                    }
+                }
+                reduced &= a[t][j] == 0;
+            }
+            if !reduced {
+                // a remainder is now smaller than the pivot and takes its place
+                continue;
+            }
+            // the pivot must divide what is left; if not, fold the offending row in
+            let offending = (t + 1..n_rows).find(|&i| a[i][t + 1..].iter().any(|x| x % pivot != 0));
+            match offending {
+                Some(i) => {
+                    let (above, below) = a.split_at_mut(i);
+                    add_row(&mut above[t][t..], 1, &below[0][t..])?;
+                }
+                None => {
+                    diagonal.push(pivot.abs());
+                    break;
+                }
+            }
+        }
+    }
+    Ok(Some(diagonal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smith_normal_form() {
+        // no unit entries, so all of it goes through the dense reduction
+        let columns = vec![
+            vec![(0, 2), (1, -6), (2, 10)],
+            vec![(0, 4), (1, 6), (2, -4)],
+            vec![(0, 4), (1, 12), (2, -16)],
+        ];
+        let form = smith_normal_form(3, columns, &mut || false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            form,
+            SmithForm {
+                rank: 3,
+                torsion: vec![2, 6, 12]
+            }
+        );
+        // a unit pivot first leaves [[2, 0], [4, 3]] behind, whose form is diag(1, 6)
+        let columns = vec![vec![(0, 1), (1, 1)], vec![(1, 2), (2, 4)], vec![(2, 3)]];
+        let form = smith_normal_form(3, columns, &mut || false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            form,
+            SmithForm {
+                rank: 3,
+                torsion: vec![6]
+            }
+        );
+        assert_eq!(
+            smith_normal_form(1, vec![vec![(0, 5)]], &mut || true),
+            Ok(None)
+        );
     }
-    num_rows - count
 }
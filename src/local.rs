@@ -0,0 +1,130 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::search::{CliqueSearch, PivotStrategy};
+use crate::visitor::CliqueVisitor;
+use std::ops::ControlFlow;
+
+/// Maximal clique queries around a few vertices, without enumerating the whole graph.
+/// The search starts with R = `include`, P = the common neighbours of `include` minus
+/// `exclude`, and X = the excluded common neighbours. Keeping the excluded vertices in X
+/// means the cliques reported are maximal in the whole graph, not just among the
+/// allowed vertices.
+pub trait LocalCliques {
+    /// Visits the maximal cliques that contain every vertex of `include` and none of
+    /// `exclude`. Nothing is reported if `include` is not itself a clique.
+    fn visit_cliques_containing<V: CliqueVisitor>(
+        &self,
+        include: &[usize],
+        exclude: &[usize],
+        pivot: PivotStrategy,
+        visitor: &mut V,
+    ) -> ControlFlow<()>;
+    /// Sorted maximal cliques containing `include` and avoiding `exclude`.
+    fn cliques_containing(&self, include: &[usize], exclude: &[usize]) -> Vec<Vec<usize>>;
+    fn cliques_with_vertex(&self, v: usize) -> Vec<Vec<usize>>;
+    fn cliques_with_edge(&self, u: usize, v: usize) -> Vec<Vec<usize>>;
+}
+
+impl LocalCliques for Bitmatrix {
+    fn visit_cliques_containing<V: CliqueVisitor>(
+        &self,
+        include: &[usize],
+        exclude: &[usize],
+        pivot: PivotStrategy,
+        visitor: &mut V,
+    ) -> ControlFlow<()> {
+        let n_nodes = self.n_nodes();
+        let mut seed = include.to_vec();
+        seed.sort_unstable();
+        seed.dedup();
+        if seed.iter().any(|v| exclude.contains(v)) {
+            return ControlFlow::Continue(());
+        }
+        let mut subg = Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes);
+        for (i, &v) in seed.iter().enumerate() {
+            if !self.matrix[v].contains_all(&seed[i + 1..]) {
+                return ControlFlow::Continue(());
+            }
+            subg.intersection_with(&self.matrix[v]);
+        }
+        let mut cand = subg.clone();
+        for &v in exclude {
+            cand.remove(v);
+        }
+        if subg.is_empty() {
+            if seed.is_empty() {
+                return ControlFlow::Continue(());
+            }
+            return visitor.visit(&seed);
+        }
+        let mut clique = seed.clone();
+        let mut search = CliqueSearch::from_sets(self, subg, cand, pivot);
+        while let Some(found) = search.next_clique(self) {
+            clique.truncate(seed.len());
+            clique.extend_from_slice(found);
+            visitor.visit(&clique)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn cliques_containing(&self, include: &[usize], exclude: &[usize]) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        let _ = self.visit_cliques_containing(
+            include,
+            exclude,
+            PivotStrategy::Tomita,
+            &mut |clique: &[usize]| {
+                let mut clique = clique.to_vec();
+                clique.sort_unstable();
+                cliques.push(clique);
+                ControlFlow::Continue(())
+            },
+        );
+        cliques
+    }
+
+    fn cliques_with_vertex(&self, v: usize) -> Vec<Vec<usize>> {
+        self.cliques_containing(&[v], &[])
+    }
+
+    fn cliques_with_edge(&self, u: usize, v: usize) -> Vec<Vec<usize>> {
+        self.cliques_containing(&[u, v], &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{maximal_cliques, pseudo_random_graph};
+
+    #[test]
+    fn test_local_queries_match_filtering() {
+        let n_nodes = 30;
+        let graph = pseudo_random_graph(n_nodes, 13, 5, 2);
+        let all = maximal_cliques(&graph);
+        let filtered = |include: &[usize], exclude: &[usize]| {
+            let mut cliques: Vec<Vec<usize>> = all
+                .iter()
+                .filter(|c| include.iter().all(|v| c.contains(v)))
+                .filter(|c| !exclude.iter().any(|v| c.contains(v)))
+                .cloned()
+                .collect();
+            cliques.sort();
+            cliques
+        };
+        for v in 0..n_nodes {
+            let mut found = graph.cliques_with_vertex(v);
+            found.sort();
+            assert_eq!(found, filtered(&[v], &[]));
+            let u = graph.get_neighbours(v).into_iter().next();
+            if let Some(u) = u {
+                let mut found = graph.cliques_with_edge(u, v);
+                found.sort();
+                assert_eq!(found, filtered(&[u, v], &[]));
+                let mut found = graph.cliques_containing(&[v], &[u]);
+                found.sort();
+                assert_eq!(found, filtered(&[v], &[u]));
+            }
+        }
+    }
+}
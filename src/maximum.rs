@@ -0,0 +1,322 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::limits::Timer;
+use std::ops::Add;
+use std::time::Duration;
+
+/// Largest clique found by the solver. `optimal` is false when the time limit ran out
+/// first, in which case `clique` is the best one found so far.
+pub struct BestClique {
+    pub clique: Vec<usize>,
+    pub optimal: bool,
+}
+
+/// Greedy sequential colouring of `pool` in vertex order, BBMC style: colour classes are
+/// built one at a time by repeatedly taking the first vertex that has no neighbour in the
+/// class yet. Vertices are returned in non-decreasing colour order together with their
+/// colour, which bounds the size of any clique among them and the vertices before them.
+/// Vertices whose colour is below `min_colour` can never improve the incumbent and are
+/// left out.
+pub(crate) fn colour_sort(
+    graph: &Bitmatrix,
+    pool: &Bitvec,
+    min_colour: usize,
+) -> Vec<(usize, usize)> {
+    let mut sorted = Vec::new();
+    let mut uncoloured = pool.clone();
+    let mut colour = 0;
+    while !uncoloured.is_empty() {
+        colour += 1;
+        let mut available = uncoloured.clone();
+        while let Some(v) = available.pop() {
+            available.difference_with(&graph.matrix[v]);
+            uncoloured.remove(v);
+            if colour >= min_colour {
+                sorted.push((v, colour));
+            }
+        }
+    }
+    sorted
+}
+
+struct Solver<'a> {
+    graph: &'a Bitmatrix,
+    clique: Vec<usize>,
+    // size of the incumbent, which may have been found before the search started
+    best_size: usize,
+    best: Option<Vec<usize>>,
+    timer: Timer,
+}
+
+impl Solver<'_> {
+    fn expand(&mut self, mut pool: Bitvec) {
+        let min_colour = (self.best_size + 1).saturating_sub(self.clique.len());
+        let sorted = colour_sort(self.graph, &pool, min_colour);
+        for &(v, colour) in sorted.iter().rev() {
+            if self.clique.len() + colour <= self.best_size || self.timer.tick() {
+                return;
+            }
+            self.clique.push(v);
+            let next = pool.intersection(&self.graph.matrix[v]);
+            if next.is_empty() {
+                if self.clique.len() > self.best_size {
+                    self.best_size = self.clique.len();
+                    self.best = Some(self.clique.clone());
+                }
+            } else {
+                self.expand(next);
+            }
+            self.clique.pop();
+            pool.remove(v);
+        }
+    }
+}
+
+/// Exact maximum clique (clique number) by bitset branch and bound, in the spirit of
+/// MCS/BBMC. A greedy clique taken along the degeneracy ordering seeds the incumbent,
+/// the k-core bound drops every vertex that cannot be part of a larger clique, and the
+/// search prunes branches with greedy colouring bounds.
+pub trait MaxClique {
+    fn maximum_clique(&self, time_limit: Option<Duration>) -> BestClique;
+}
+
+impl MaxClique for Bitmatrix {
+    fn maximum_clique(&self, time_limit: Option<Duration>) -> BestClique {
+        let ordering = self.degeneracy_ordering();
+        // vertices peeled off last sit in the densest core, start the greedy clique there
+        let mut best: Vec<usize> = Vec::new();
+        for &v in ordering.order.iter().rev() {
+            if self.matrix[v].contains_all(&best) {
+                best.push(v);
+            }
+        }
+        // a clique of size s only lives inside the (s-1)-core
+        let vertices: Vec<usize> = ordering
+            .order
+            .iter()
+            .rev()
+            .copied()
+            .filter(|&v| ordering.core_numbers[v] >= best.len())
+            .collect();
+        let subgraph = self.induced_subgraph(&vertices);
+        let mut solver = Solver {
+            graph: &subgraph,
+            clique: Vec::new(),
+            best_size: best.len(),
+            best: None,
+            timer: Timer::new(time_limit),
+        };
+        let mut pool = Bitvec::new(vertices.len());
+        for i in 0..vertices.len() {
+            pool.insert(i);
+        }
+        solver.expand(pool);
+        if let Some(found) = solver.best {
+            best = found.iter().map(|&i| vertices[i]).collect();
+        }
+        best.sort_unstable();
+        BestClique {
+            clique: best,
+            optimal: !solver.timer.expired(),
+        }
+    }
+}
+
+/// Vertex weights accepted by the weighted solver, e.g. `i64`, `u64` or `f64`.
+pub trait Weight: Copy + PartialOrd + Add<Output = Self> + Default {}
+
+impl<W: Copy + PartialOrd + Add<Output = W> + Default> Weight for W {}
+
+/// Heaviest clique found by the weighted solver, with the same time-limit semantics as
+/// `BestClique`.
+pub struct BestWeightedClique<W> {
+    pub clique: Vec<usize>,
+    pub weight: W,
+    pub optimal: bool,
+}
+
+/// Weighted version of `colour_sort`: every vertex comes with the sum, over its own colour
+/// class and all the earlier ones, of the heaviest weight in each class. A clique takes at
+/// most one vertex per class, so that sum bounds the weight of any clique drawn from the
+/// vertex and the ones sorted before it.
+fn weighted_colour_sort<W: Weight>(
+    graph: &Bitmatrix,
+    pool: &Bitvec,
+    weights: &[W],
+) -> Vec<(usize, W)> {
+    let mut sorted = Vec::new();
+    let mut uncoloured = pool.clone();
+    let mut bound = W::default();
+    while !uncoloured.is_empty() {
+        let mut class = Vec::new();
+        let mut available = uncoloured.clone();
+        while let Some(v) = available.pop() {
+            available.difference_with(&graph.matrix[v]);
+            uncoloured.remove(v);
+            class.push(v);
+        }
+        let heaviest = class
+            .iter()
+            .map(|&v| weights[v])
+            .fold(W::default(), |a, b| if b > a { b } else { a });
+        bound = bound + heaviest;
+        sorted.extend(class.into_iter().map(|v| (v, bound)));
+    }
+    sorted
+}
+
+struct WeightedSolver<'a, W> {
+    graph: &'a Bitmatrix,
+    weights: &'a [W],
+    clique: Vec<usize>,
+    best_weight: W,
+    best: Option<Vec<usize>>,
+    timer: Timer,
+}
+
+impl<W: Weight> WeightedSolver<'_, W> {
+    fn expand(&mut self, weight: W, mut pool: Bitvec) {
+        let sorted = weighted_colour_sort(self.graph, &pool, self.weights);
+        for &(v, bound) in sorted.iter().rev() {
+            if weight + bound <= self.best_weight || self.timer.tick() {
+                return;
+            }
+            self.clique.push(v);
+            let extended = weight + self.weights[v];
+            // with positive weights every clique is a candidate, not only maximal ones
+            if extended > self.best_weight {
+                self.best_weight = extended;
+                self.best = Some(self.clique.clone());
+            }
+            let next = pool.intersection(&self.graph.matrix[v]);
+            if !next.is_empty() {
+                self.expand(extended, next);
+            }
+            self.clique.pop();
+            pool.remove(v);
+        }
+    }
+}
+
+/// Maximum weight clique by bitset branch and bound with weighted colouring bounds.
+/// Vertices of non-positive weight never make a clique heavier, so they are left out of
+/// the search; if no weight is positive the answer is the single heaviest vertex. Every
+/// sum formed is at most the total positive weight, which `W` must be able to hold.
+pub trait MaxWeightClique {
+    fn maximum_weight_clique<W: Weight>(
+        &self,
+        weights: &[W],
+        time_limit: Option<Duration>,
+    ) -> BestWeightedClique<W>;
+}
+
+impl MaxWeightClique for Bitmatrix {
+    fn maximum_weight_clique<W: Weight>(
+        &self,
+        weights: &[W],
+        time_limit: Option<Duration>,
+    ) -> BestWeightedClique<W> {
+        assert_eq!(
+            weights.len(),
+            self.n_nodes(),
+            "There must be one weight per vertex"
+        );
+        // heavier vertices first, both for the greedy incumbent and the colouring order
+        let mut vertices: Vec<usize> = (0..self.n_nodes())
+            .filter(|&v| weights[v] > W::default())
+            .collect();
+        vertices.sort_by(|&a, &b| {
+            weights[b]
+                .partial_cmp(&weights[a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        if vertices.is_empty() {
+            let heaviest =
+                (0..self.n_nodes()).reduce(|a, b| if weights[b] > weights[a] { b } else { a });
+            return BestWeightedClique {
+                clique: heaviest.into_iter().collect(),
+                weight: heaviest.map_or(W::default(), |v| weights[v]),
+                optimal: true,
+            };
+        }
+        let mut best: Vec<usize> = Vec::new();
+        let mut best_weight = W::default();
+        for &v in vertices.iter() {
+            if self.matrix[v].contains_all(&best) {
+                best.push(v);
+                best_weight = best_weight + weights[v];
+            }
+        }
+        let subgraph = self.induced_subgraph(&vertices);
+        let local_weights: Vec<W> = vertices.iter().map(|&v| weights[v]).collect();
+        let mut solver = WeightedSolver {
+            graph: &subgraph,
+            weights: &local_weights,
+            clique: Vec::new(),
+            best_weight,
+            best: None,
+            timer: Timer::new(time_limit),
+        };
+        let mut pool = Bitvec::new(vertices.len());
+        for i in 0..vertices.len() {
+            pool.insert(i);
+        }
+        solver.expand(W::default(), pool);
+        if let Some(found) = solver.best {
+            best = found.iter().map(|&i| vertices[i]).collect();
+        }
+        best.sort_unstable();
+        BestWeightedClique {
+            clique: best,
+            weight: solver.best_weight,
+            optimal: !solver.timer.expired(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{maximal_cliques, pseudo_random_graph};
+
+    #[test]
+    fn test_maximum_clique() {
+        let n_nodes = 45;
+        let graph = pseudo_random_graph(n_nodes, 17, 5, 3);
+        let expected = maximal_cliques(&graph).iter().map(|c| c.len()).max();
+        let best = graph.maximum_clique(None);
+        assert!(best.optimal);
+        assert_eq!(Some(best.clique.len()), expected);
+        for (i, &u) in best.clique.iter().enumerate() {
+            assert!(graph.matrix[u].contains_all(&best.clique[i + 1..]));
+        }
+        let best = graph.maximum_clique(Some(Duration::MAX));
+        assert!(best.optimal);
+        assert_eq!(Some(best.clique.len()), expected);
+    }
+    #[test]
+    fn test_maximum_weight_clique() {
+        // a heavy edge beats a light triangle
+        let rows = vec![vec![1, 2], vec![0, 2], vec![0, 1], vec![4], vec![3]];
+        let graph = Bitmatrix::new(rows, 5);
+        let best = graph.maximum_weight_clique(&[1, 1, 1, 2, 2], None);
+        assert_eq!(
+            (best.clique, best.weight, best.optimal),
+            (vec![3, 4], 4, true)
+        );
+        let best = graph.maximum_weight_clique(&[1.5, 1.5, 1.5, 2.0, 0.5], None);
+        assert_eq!((best.clique, best.weight), (vec![0, 1, 2], 4.5));
+        let best = graph.maximum_weight_clique(&[-3, -1, -2, -5, -4], None);
+        assert_eq!((best.clique, best.weight), (vec![1], -1));
+        let best = graph.maximum_weight_clique(&[1, 1, 1, 2, 2], Some(Duration::MAX));
+        assert_eq!((best.clique, best.optimal), (vec![3, 4], true));
+    }
+    #[test]
+    fn test_maximum_clique_edgeless() {
+        let graph = Bitmatrix::new(vec![vec![]; 3], 3);
+        assert_eq!(graph.maximum_clique(None).clique.len(), 1);
+        let graph = Bitmatrix::new(vec![], 0);
+        assert!(graph.maximum_clique(None).clique.is_empty());
+    }
+}
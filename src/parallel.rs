@@ -0,0 +1,451 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::limits::{Limits, Outcome, StopReason, CHECK_INTERVAL};
+use crate::search::{CliqueSearch, PivotStrategy, SearchStats, Step};
+use rayon::prelude::*;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Subproblems with more candidates than this are split into their branches and handed
+/// to the pool; smaller ones are cheaper to finish on the thread that found them.
+const SPLIT_THRESHOLD: usize = 48;
+
+/// Stop conditions shared by every task of a parallel run. The first reason recorded
+/// wins; the others only make the workers notice sooner.
+struct Halt<'a> {
+    stop: AtomicBool,
+    cancel: &'a AtomicBool,
+    deadline: Option<Instant>,
+    max_results: Option<u64>,
+    results: AtomicU64,
+    reason: Mutex<Option<StopReason>>,
+    stats: Mutex<SearchStats>,
+}
+
+impl Halt<'_> {
+    fn halt(&self, reason: StopReason) {
+        self.reason.lock().unwrap().get_or_insert(reason);
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    fn stopped(&self) -> bool {
+        if self.cancel.load(Ordering::Relaxed) {
+            self.halt(StopReason::Interrupted);
+        }
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Like `stopped`, but also looks at the clock.
+    fn check(&self) -> bool {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.halt(StopReason::TimeBudget);
+        }
+        self.stopped()
+    }
+}
+
+/// Shared state of one parallel run: every clique is folded into a per-task accumulator
+/// which are then reduced pairwise, rayon style. Size bounds count the vertices of the
+/// whole clique, `offset` of which are added by the caller of `explore`. Excluded vertices
+/// kept outside the graph are as in `CliqueSearch::from_sets_excluding`.
+struct Run<'a, T, F, R> {
+    pivot: PivotStrategy,
+    excluded: Arc<[Bitvec]>,
+    halt: &'a Halt<'a>,
+    min_size: usize,
+    max_size: Option<usize>,
+    offset: usize,
+    fold: &'a F,
+    reduce: &'a R,
+    _acc: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T, F, R> Run<'_, T, F, R>
+where
+    T: Send + Default,
+    F: Fn(&mut T, &[usize]) -> ControlFlow<()> + Sync,
+    R: Fn(T, T) -> T + Sync,
+{
+    fn emit(&self, acc: &mut T, clique: &[usize]) -> bool {
+        if self.halt.stopped() {
+            return false;
+        }
+        // claim a slot first so that concurrent workers never overshoot `max_results`
+        let claimed = self.halt.results.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.halt.max_results.is_some_and(|max| claimed > max) {
+            self.halt.halt(StopReason::MaxResults);
+            return false;
+        }
+        if (self.fold)(acc, clique).is_break() {
+            // the clique was refused, give its slot back
+            self.halt.results.fetch_sub(1, Ordering::Relaxed);
+            self.halt.halt(StopReason::Visitor);
+            return false;
+        }
+        if self.halt.max_results == Some(claimed) {
+            self.halt.halt(StopReason::MaxResults);
+            return false;
+        }
+        true
+    }
+
+    fn size_fits(&self, size: usize) -> bool {
+        let size = size + self.offset;
+        size >= self.min_size && self.max_size.is_none_or(|max| size <= max)
+    }
+
+    /// Whether a clique of `size` vertices with `room` candidates left can still grow
+    /// into a maximal clique within the size bounds.
+    fn can_grow(&self, size: usize, room: usize) -> bool {
+        let size = size + self.offset;
+        size + room >= self.min_size && self.max_size.is_none_or(|max| size < max)
+    }
+
+    /// Explores the subproblem below `prefix`, splitting it across the pool while it is
+    /// large and finishing it sequentially once it is small.
+    fn explore(
+        &self,
+        graph: &Bitmatrix,
+        prefix: &[usize],
+        subg: Bitvec,
+        cand: Bitvec,
+        outside: Bitvec,
+        top: bool,
+    ) -> T {
+        let mut acc = T::default();
+        if self.halt.check() {
+            return acc;
+        }
+        let depth = prefix.len() + self.offset;
+        let search = CliqueSearch::from_sets_excluding(
+            graph,
+            subg,
+            cand.clone(),
+            self.excluded.clone(),
+            outside,
+            self.pivot,
+        );
+        if !top && cand.n_elements() <= SPLIT_THRESHOLD {
+            let mut search = search.with_size_bounds(
+                self.min_size.saturating_sub(depth),
+                self.max_size.map(|max| max.saturating_sub(depth)),
+            );
+            let mut clique = prefix.to_vec();
+            loop {
+                match search.step(graph, CHECK_INTERVAL) {
+                    Step::Clique(found) => {
+                        clique.truncate(prefix.len());
+                        clique.extend_from_slice(found);
+                        if !self.emit(&mut acc, &clique) {
+                            break;
+                        }
+                    }
+                    Step::Paused if self.halt.check() => break,
+                    Step::Paused => {}
+                    Step::Exhausted => break,
+                }
+            }
+            self.halt
+                .stats
+                .lock()
+                .unwrap()
+                .merge(&search.stats(), depth);
+            return acc;
+        }
+        self.halt
+            .stats
+            .lock()
+            .unwrap()
+            .merge(&search.stats(), depth);
+        search
+            .into_branches(graph)
+            .into_par_iter()
+            .map(|branch| {
+                let mut acc = T::default();
+                let mut clique = prefix.to_vec();
+                clique.push(branch.vertex);
+                let size = clique.len();
+                let n_cand = branch.cand.n_elements();
+                self.halt
+                    .stats
+                    .lock()
+                    .unwrap()
+                    .record_node(size + self.offset, n_cand);
+                if branch.subg.is_empty() {
+                    if self.size_fits(size) {
+                        self.emit(&mut acc, &clique);
+                    }
+                    acc
+                } else if self.can_grow(size, n_cand) {
+                    self.explore(
+                        graph,
+                        &clique,
+                        branch.subg,
+                        branch.cand,
+                        branch.outside,
+                        false,
+                    )
+                } else {
+                    acc
+                }
+            })
+            .reduce(T::default, self.reduce)
+    }
+}
+
+fn full_set(n_nodes: usize) -> Bitvec {
+    Bitvec::from_vector(&(0..n_nodes).collect::<Vec<usize>>(), n_nodes)
+}
+
+/// Runs `job` on a dedicated pool with `threads` workers (0 means one per core).
+fn with_threads<T: Send>(threads: usize, job: impl FnOnce() -> T + Send) -> T {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("failed to start the clique thread pool")
+        .install(job)
+}
+
+/// Maximal clique enumeration spread over a work-stealing pool. The top level is always
+/// split into its branches, deeper subproblems only while they are large, so idle
+/// workers steal whole subtrees from busy ones. Cliques reach the visitor in no
+/// particular order.
+pub trait ParallelCliques {
+    /// Folds every maximal clique into per-task accumulators and reduces them, under
+    /// `Limits`. `sparse` selects the degeneracy-ordered (Eppstein–Löffler–Strash)
+    /// decomposition, where each vertex's neighbourhood becomes an independent task.
+    /// Setting `cancel` from another thread stops the run as an interruption, and `fold`
+    /// returning `ControlFlow::Break` stops it as the visitor's doing; the partial result
+    /// comes with the outcome of the run, including the reason it stopped early if it did.
+    #[allow(clippy::too_many_arguments)]
+    fn par_fold_cliques_limited<T, F, R>(
+        &self,
+        sparse: bool,
+        pivot: PivotStrategy,
+        threads: usize,
+        limits: &Limits,
+        cancel: &AtomicBool,
+        fold: F,
+        reduce: R,
+    ) -> (T, Outcome)
+    where
+        T: Send + Default,
+        F: Fn(&mut T, &[usize]) -> ControlFlow<()> + Sync,
+        R: Fn(T, T) -> T + Sync;
+}
+
+impl ParallelCliques for Bitmatrix {
+    fn par_fold_cliques_limited<T, F, R>(
+        &self,
+        sparse: bool,
+        pivot: PivotStrategy,
+        threads: usize,
+        limits: &Limits,
+        cancel: &AtomicBool,
+        fold: F,
+        reduce: R,
+    ) -> (T, Outcome)
+    where
+        T: Send + Default,
+        F: Fn(&mut T, &[usize]) -> ControlFlow<()> + Sync,
+        R: Fn(T, T) -> T + Sync,
+    {
+        let halt = Halt {
+            stop: AtomicBool::new(false),
+            cancel,
+            deadline: limits.deadline(),
+            max_results: limits.max_results,
+            results: AtomicU64::new(0),
+            reason: Mutex::new(None),
+            stats: Mutex::new(SearchStats::default()),
+        };
+        let run = Run {
+            pivot,
+            excluded: Arc::new([]),
+            halt: &halt,
+            min_size: limits.min_size,
+            max_size: limits.max_size,
+            offset: 0,
+            fold: &fold,
+            reduce: &reduce,
+            _acc: std::marker::PhantomData,
+        };
+        let n_nodes = self.n_nodes();
+        let result = with_threads(threads, || {
+            if !sparse {
+                let (subg, cand) = (full_set(n_nodes), full_set(n_nodes));
+                return run.explore(self, &[], subg, cand, Bitvec::new(0), true);
+            }
+            let ordering = self.degeneracy_ordering();
+            ordering
+                .order
+                .par_iter()
+                .map(|&v| {
+                    let mut acc = T::default();
+                    if halt.check() {
+                        return acc;
+                    }
+                    let local = self.local_neighbourhood(v, &ordering.position);
+                    let n_later = local.later.len();
+                    halt.stats.lock().unwrap().record_node(1, n_later);
+                    if local.is_isolated() {
+                        if run.size_fits(1) {
+                            run.emit(&mut acc, &[v]);
+                        }
+                        return acc;
+                    }
+                    if !run.can_grow(1, n_later) {
+                        return acc;
+                    }
+                    // the local run reports local ids, translate them before folding
+                    let translate = |acc: &mut T, found: &[usize]| {
+                        let mut clique = Vec::with_capacity(found.len() + 1);
+                        clique.push(v);
+                        clique.extend(found.iter().map(|&i| local.later[i]));
+                        fold(acc, &clique)
+                    };
+                    let local_run = Run {
+                        pivot,
+                        excluded: local.earlier.clone(),
+                        halt: &halt,
+                        min_size: limits.min_size,
+                        max_size: limits.max_size,
+                        offset: 1,
+                        fold: &translate,
+                        reduce: &reduce,
+                        _acc: std::marker::PhantomData,
+                    };
+                    let outside = full_set(local.earlier.len());
+                    let (subg, cand) = (full_set(n_later), full_set(n_later));
+                    local_run.explore(&local.graph, &[], subg, cand, outside, false)
+                })
+                .reduce(T::default, &reduce)
+        });
+        let results = halt.results.load(Ordering::Relaxed);
+        let outcome = Outcome {
+            results: limits.max_results.map_or(results, |max| results.min(max)),
+            stopped: *halt.reason.lock().unwrap(),
+            stats: *halt.stats.lock().unwrap(),
+        };
+        (result, outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{graph_where, maximal_cliques, pseudo_random_graph};
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let n_nodes = 40;
+        let graph = pseudo_random_graph(n_nodes, 31, 7, 3);
+        let mut expected = maximal_cliques(&graph);
+        expected.sort();
+        let cancel = AtomicBool::new(false);
+        for sparse in [false, true] {
+            let (mut found, outcome) = graph.par_fold_cliques_limited(
+                sparse,
+                PivotStrategy::Tomita,
+                4,
+                &Limits::default(),
+                &cancel,
+                |cliques: &mut Vec<Vec<usize>>, clique| {
+                    let mut clique = clique.to_vec();
+                    clique.sort_unstable();
+                    cliques.push(clique);
+                    ControlFlow::Continue(())
+                },
+                |mut a, mut b| {
+                    a.append(&mut b);
+                    a
+                },
+            );
+            found.sort();
+            assert_eq!(found, expected);
+            assert_eq!(outcome.stopped, None);
+        }
+    }
+    #[test]
+    fn test_parallel_limits() {
+        let n_nodes = 40;
+        let graph = pseudo_random_graph(n_nodes, 31, 7, 3);
+        let mut expected: Vec<Vec<usize>> = maximal_cliques(&graph)
+            .into_iter()
+            .filter(|c| (3..=4).contains(&c.len()))
+            .collect();
+        expected.sort();
+        let cancel = AtomicBool::new(false);
+        for sparse in [false, true] {
+            let limits = Limits {
+                min_size: 3,
+                max_size: Some(4),
+                ..Limits::default()
+            };
+            let (mut found, outcome) = graph.par_fold_cliques_limited(
+                sparse,
+                PivotStrategy::Tomita,
+                3,
+                &limits,
+                &cancel,
+                |cliques: &mut Vec<Vec<usize>>, clique| {
+                    let mut clique = clique.to_vec();
+                    clique.sort_unstable();
+                    cliques.push(clique);
+                    ControlFlow::Continue(())
+                },
+                |mut a, mut b| {
+                    a.append(&mut b);
+                    a
+                },
+            );
+            found.sort();
+            assert_eq!(found, expected);
+            assert_eq!(outcome.stopped, None);
+            assert_eq!(outcome.results, expected.len() as u64);
+
+            let limits = Limits {
+                max_results: Some(7),
+                ..Limits::default()
+            };
+            let (count, outcome) = graph.par_fold_cliques_limited(
+                sparse,
+                PivotStrategy::Degree,
+                3,
+                &limits,
+                &cancel,
+                |count: &mut usize, _| {
+                    *count += 1;
+                    ControlFlow::Continue(())
+                },
+                |a, b| a + b,
+            );
+            assert_eq!(count, 7);
+            assert_eq!(outcome.results, 7);
+            assert_eq!(outcome.stopped, Some(StopReason::MaxResults));
+        }
+    }
+
+    #[test]
+    fn test_parallel_visit_stops() {
+        let n_nodes = 60;
+        let graph = graph_where(n_nodes, |u, v| (u + v) % 3 != 0);
+        let (_, outcome) = graph.par_fold_cliques_limited(
+            false,
+            PivotStrategy::Degree,
+            2,
+            &Limits::default(),
+            &AtomicBool::new(false),
+            |_: &mut (), _: &[usize]| ControlFlow::Break(()),
+            |_, _| (),
+        );
+        assert_eq!(outcome.stopped, Some(StopReason::Visitor));
+        assert_eq!(outcome.results, 0);
+    }
+}
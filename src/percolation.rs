@@ -0,0 +1,150 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::cliquegraph::clique_overlaps;
+use crate::degeneracy::SparseCliques;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Union-find over clique indices, with path halving and union by size.
+struct DisjointSets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        DisjointSets {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Sorted union of the vertices of each group of cliques, the groups sorted too.
+fn communities(cliques: &[Vec<usize>], groups: BTreeMap<usize, Vec<usize>>) -> Vec<Vec<usize>> {
+    let mut communities: Vec<Vec<usize>> = groups
+        .into_values()
+        .map(|members| {
+            let mut community: Vec<usize> = members
+                .iter()
+                .flat_map(|&i| cliques[i].iter().copied())
+                .collect();
+            community.sort_unstable();
+            community.dedup();
+            community
+        })
+        .collect();
+    communities.sort();
+    communities
+}
+
+/// k-clique communities (Palla et al. clique percolation). A community is the union of
+/// k-cliques that can be reached from one another through k-cliques sharing k - 1
+/// vertices. Rather than listing k-cliques, the percolation runs over maximal cliques:
+/// two maximal cliques with at least k vertices belong to the same community exactly
+/// when they are chained by overlaps of at least k - 1 vertices.
+pub trait CliquePercolation {
+    /// Communities for one `k` (at least 2), each a sorted list of vertices.
+    fn k_clique_communities(&self, k: usize) -> Vec<Vec<usize>>;
+    /// Communities for every `k` in `ks` from a single clique enumeration. The overlaps
+    /// are merged from the largest `k` down, so each smaller `k` only adds unions.
+    fn k_clique_communities_range(
+        &self,
+        ks: RangeInclusive<usize>,
+    ) -> BTreeMap<usize, Vec<Vec<usize>>>;
+}
+
+impl CliquePercolation for Bitmatrix {
+    fn k_clique_communities(&self, k: usize) -> Vec<Vec<usize>> {
+        self.k_clique_communities_range(k..=k)
+            .remove(&k)
+            .unwrap_or_default()
+    }
+
+    fn k_clique_communities_range(
+        &self,
+        ks: RangeInclusive<usize>,
+    ) -> BTreeMap<usize, Vec<Vec<usize>>> {
+        assert!(*ks.start() >= 2, "k-clique communities need k >= 2");
+        let k_min = *ks.start();
+        let mut cliques = self.maximal_cliques_sparse();
+        cliques.retain(|clique| clique.len() >= k_min);
+        // larger cliques first, so each k activates a prefix of them
+        cliques.sort_by_key(|clique| std::cmp::Reverse(clique.len()));
+        let mut overlaps = clique_overlaps(&cliques, self.n_nodes(), k_min - 1);
+        overlaps.sort_by_key(|&(_, _, overlap)| std::cmp::Reverse(overlap));
+        let mut sets = DisjointSets::new(cliques.len());
+        let mut result = BTreeMap::new();
+        let (mut active, mut merged) = (0, 0);
+        for k in ks.rev() {
+            while active < cliques.len() && cliques[active].len() >= k {
+                active += 1;
+            }
+            // an overlap of k - 1 vertices implies both cliques have at least k
+            while merged < overlaps.len() && overlaps[merged].2 + 1 >= k {
+                let (i, j, _) = overlaps[merged];
+                sets.union(i, j);
+                merged += 1;
+            }
+            let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for i in 0..active {
+                groups.entry(sets.find(i)).or_default().push(i);
+            }
+            result.insert(k, communities(&cliques, groups));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::graph_from_edges;
+
+    #[test]
+    fn test_k_clique_communities() {
+        // two K4s sharing the triangle 2-3-4, a triangle hanging off vertex 5 and an
+        // isolated edge
+        let mut edges = Vec::new();
+        for clique in [[0, 2, 3, 4], [1, 2, 3, 4]] {
+            for a in 0..4 {
+                for b in a + 1..4 {
+                    edges.push((clique[a], clique[b]));
+                }
+            }
+        }
+        edges.extend([(4, 5), (5, 6), (5, 7), (6, 7), (8, 9)]);
+        let graph = graph_from_edges(&edges, 10);
+        assert_eq!(
+            graph.k_clique_communities(3),
+            vec![vec![0, 1, 2, 3, 4], vec![5, 6, 7]]
+        );
+        let communities = graph.k_clique_communities_range(2..=5);
+        assert_eq!(
+            communities[&2],
+            vec![vec![0, 1, 2, 3, 4, 5, 6, 7], vec![8, 9]]
+        );
+        assert_eq!(communities[&3], graph.k_clique_communities(3));
+        assert_eq!(communities[&4], vec![vec![0, 1, 2, 3, 4]]);
+        assert!(communities[&5].is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+use crate::bitmatrix::Bitmatrix;
+use crate::bitvec::Bitvec;
+use crate::degeneracy::Degeneracy;
+use crate::limits::{Budget, Limits, Outcome, StopReason, CHECK_INTERVAL};
+use crate::visitor::CliqueVisitor;
+use std::ops::ControlFlow;
+
+/// Vertices of the `threshold`-core, the only ones that can belong to a dense set whose
+/// members all need at least `threshold` neighbours inside it.
+fn core_vertices(graph: &Bitmatrix, threshold: usize) -> Vec<usize> {
+    let core_numbers = graph.degeneracy_ordering().core_numbers;
+    (0..graph.n_nodes())
+        .filter(|&v| core_numbers[v] >= threshold)
+        .collect()
+}
+
+/// Maximal k-plex search, Bron–Kerbosch style: k-plexes are hereditary, so R grows one
+/// candidate at a time and a set is reported once neither a candidate nor an excluded
+/// vertex can join it. `missing[v]` counts the vertices of R that `v` is not adjacent
+/// to, itself included; a vertex of R missing `k` of them is saturated and every new
+/// vertex must be its neighbour.
+struct PlexSearch<'a, 'b, V> {
+    graph: &'a Bitmatrix,
+    labels: &'a [usize],
+    k: usize,
+    min_size: usize,
+    max_size: Option<usize>,
+    plex: Vec<usize>,
+    members: Bitvec,
+    missing: Vec<usize>,
+    nodes: usize,
+    budget: Budget<'b>,
+    visitor: &'a mut V,
+}
+
+impl<V: CliqueVisitor> PlexSearch<'_, '_, V> {
+    /// Whether `u` can join the current plex.
+    fn fits(&self, u: usize) -> bool {
+        let adjacent = &self.graph.matrix[u];
+        let missed = self.plex.len() - self.members.intersection_count(adjacent);
+        missed < self.k
+            && self
+                .plex
+                .iter()
+                .all(|&w| self.missing[w] < self.k || adjacent.contains(w))
+    }
+
+    fn filter(&self, set: &Bitvec) -> Bitvec {
+        let mut kept = Bitvec::new(self.graph.n_nodes());
+        for u in set.elements() {
+            if self.fits(u) {
+                kept.insert(u);
+            }
+        }
+        kept
+    }
+
+    fn add(&mut self, v: usize) {
+        let adjacent = &self.graph.matrix[v];
+        for &w in &self.plex {
+            if !adjacent.contains(w) {
+                self.missing[w] += 1;
+            }
+        }
+        self.missing[v] = self.plex.len() + 1 - self.members.intersection_count(adjacent);
+        self.plex.push(v);
+        self.members.insert(v);
+    }
+
+    fn remove(&mut self, v: usize) {
+        self.plex.pop();
+        self.members.remove(v);
+        let adjacent = &self.graph.matrix[v];
+        for &w in &self.plex {
+            if !adjacent.contains(w) {
+                self.missing[w] -= 1;
+            }
+        }
+    }
+
+    fn expand(&mut self, mut cand: Bitvec, mut excl: Bitvec) -> ControlFlow<()> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.budget.check()?;
+        }
+        if cand.is_empty() {
+            if excl.is_empty() && self.plex.len() >= self.min_size {
+                let mut plex: Vec<usize> = self.plex.iter().map(|&v| self.labels[v]).collect();
+                plex.sort_unstable();
+                self.budget.report(self.visitor, &plex)?;
+            }
+            return ControlFlow::Continue(());
+        }
+        // every maximal plex below would be larger than allowed
+        if self.max_size.is_some_and(|max| self.plex.len() >= max) {
+            return ControlFlow::Continue(());
+        }
+        for v in cand.elements() {
+            if self.plex.len() + cand.n_elements() < self.min_size {
+                break;
+            }
+            cand.remove(v);
+            self.add(v);
+            let next_cand = self.filter(&cand);
+            let next_excl = self.filter(&excl);
+            let flow = self.expand(next_cand, next_excl);
+            self.remove(v);
+            flow?;
+            excl.insert(v);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Quasi-clique search: every subset of the candidates is reached once by adding
+/// vertices in increasing order. Quasi-cliques are not hereditary, so sets that fail the
+/// density test are still extended; branches are cut with degree bounds, and for
+/// `gamma >= 0.5` with the fact that such quasi-cliques have diameter at most 2.
+///
+/// Only the sets maximal among the quasi-cliques found so far are kept: a set is dropped
+/// when a quasi-clique was found below it in the search, which extends it, and otherwise
+/// compared with the kept ones.
+struct QuasiSearch<'a, 'b> {
+    graph: &'a Bitmatrix,
+    gamma: f64,
+    min_size: usize,
+    // vertices within distance 2 of each vertex, when the diameter bound applies
+    two_hop: Option<Vec<Bitvec>>,
+    set: Vec<usize>,
+    members: Bitvec,
+    maximal: Vec<Bitvec>,
+    // quasi-cliques seen so far, kept or not
+    seen: u64,
+    nodes: usize,
+    budget: Budget<'b>,
+}
+
+impl QuasiSearch<'_, '_> {
+    /// Fewest neighbours each member of a quasi-clique with `size` vertices needs.
+    fn min_degree(&self, size: usize) -> usize {
+        (self.gamma * size.saturating_sub(1) as f64 - 1e-9)
+            .ceil()
+            .max(0.0) as usize
+    }
+
+    /// Most neighbours `v` could end up with inside the set if every candidate joined.
+    fn reachable_degree(&self, v: usize, cand: &Bitvec) -> usize {
+        let adjacent = &self.graph.matrix[v];
+        self.members.intersection_count(adjacent) + cand.intersection_count(adjacent)
+    }
+
+    /// Keeps `set` unless a kept set contains it, dropping the kept sets it contains.
+    fn keep(&mut self, set: Bitvec) {
+        let size = set.n_elements();
+        if self
+            .maximal
+            .iter()
+            .any(|big| big.intersection_count(&set) == size)
+        {
+            return;
+        }
+        self.maximal
+            .retain(|small| small.intersection_count(&set) != small.n_elements());
+        self.maximal.push(set);
+    }
+
+    fn expand(&mut self, cand: Bitvec) -> ControlFlow<()> {
+        for v in cand.elements() {
+            self.nodes += 1;
+            if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+                self.budget.check()?;
+            }
+            self.set.push(v);
+            self.members.insert(v);
+            let mut later = Bitvec::from_vector(&cand.elements_from(v), self.graph.n_nodes());
+            if let Some(two_hop) = &self.two_hop {
+                later.intersection_with(&two_hop[v]);
+            }
+            // candidates that could never reach the degree they would need
+            let needed = self.min_degree(self.min_size.max(self.set.len() + 1));
+            let next: Vec<usize> = later
+                .elements()
+                .into_iter()
+                .filter(|&u| self.reachable_degree(u, &later) >= needed)
+                .collect();
+            let next = Bitvec::from_vector(&next, self.graph.n_nodes());
+            let needed = self.min_degree(self.min_size.max(self.set.len()));
+            let viable = self
+                .set
+                .iter()
+                .all(|&w| self.reachable_degree(w, &next) >= needed);
+            if viable {
+                let size = self.set.len();
+                let dense = self.set.iter().all(|&w| {
+                    self.members.intersection_count(&self.graph.matrix[w]) >= self.min_degree(size)
+                });
+                let seen = self.seen;
+                let mut flow = ControlFlow::Continue(());
+                if size + next.n_elements() >= self.min_size.max(size + 1) {
+                    flow = self.expand(next);
+                }
+                if dense && size >= self.min_size {
+                    if self.seen == seen {
+                        self.keep(self.members.clone());
+                    }
+                    self.seen += 1;
+                }
+                if flow.is_break() {
+                    self.set.pop();
+                    self.members.remove(v);
+                    return flow;
+                }
+            }
+            self.set.pop();
+            self.members.remove(v);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Relaxations of cliques for dense groups with a few missing edges.
+pub trait RelaxedCliques {
+    /// Visits the maximal k-plexes with at least `limits.min_size` (and at most
+    /// `limits.max_size`) vertices: sets in which every vertex is adjacent to all but at
+    /// most `k` of the set's vertices, itself included, so 1-plexes are cliques.
+    fn visit_k_plexes_limited<V: CliqueVisitor>(
+        &self,
+        k: usize,
+        limits: &Limits,
+        interrupt: &mut dyn FnMut() -> bool,
+        visitor: &mut V,
+    ) -> Outcome;
+    /// Maximal γ-quasi-cliques with at least `limits.min_size` (and at most
+    /// `limits.max_size`) vertices: sets in which every vertex is adjacent to at least
+    /// `gamma * (size - 1)` of the others, and that are not contained in a larger one.
+    /// Quasi-cliques are not hereditary, so maximality is only known once the search
+    /// ends: `limits.max_size` filters the maximal sets rather than cutting the search,
+    /// and `limits.max_results` only truncates the final list. If the time budget or
+    /// `interrupt` stops the search, the sets returned are maximal among those found so
+    /// far.
+    fn quasi_cliques_limited(
+        &self,
+        gamma: f64,
+        limits: &Limits,
+        interrupt: &mut dyn FnMut() -> bool,
+    ) -> (Vec<Vec<usize>>, Outcome);
+}
+
+impl RelaxedCliques for Bitmatrix {
+    fn visit_k_plexes_limited<V: CliqueVisitor>(
+        &self,
+        k: usize,
+        limits: &Limits,
+        interrupt: &mut dyn FnMut() -> bool,
+        visitor: &mut V,
+    ) -> Outcome {
+        assert!(k >= 1, "k-plexes need k >= 1");
+        let vertices = core_vertices(self, limits.min_size.saturating_sub(k));
+        let subgraph = self.induced_subgraph(&vertices);
+        let n_local = vertices.len();
+        let mut search = PlexSearch {
+            graph: &subgraph,
+            labels: &vertices,
+            k,
+            min_size: limits.min_size,
+            max_size: limits.max_size,
+            plex: Vec::new(),
+            members: Bitvec::new(n_local),
+            missing: vec![0; n_local],
+            nodes: 0,
+            budget: Budget::new(limits, interrupt),
+            visitor,
+        };
+        if n_local > 0 {
+            let cand = Bitvec::from_vector(&(0..n_local).collect::<Vec<usize>>(), n_local);
+            if search.budget.check().is_continue() {
+                let _ = search.expand(cand, Bitvec::new(n_local));
+            }
+        }
+        search.budget.outcome()
+    }
+
+    fn quasi_cliques_limited(
+        &self,
+        gamma: f64,
+        limits: &Limits,
+        interrupt: &mut dyn FnMut() -> bool,
+    ) -> (Vec<Vec<usize>>, Outcome) {
+        assert!(
+            gamma > 0.0 && gamma <= 1.0,
+            "gamma must lie in (0, 1], got {}",
+            gamma
+        );
+        // single vertices are trivially dense, start from pairs at least
+        let min_size = limits.min_size.max(2);
+        let threshold = (gamma * (min_size - 1) as f64 - 1e-9).ceil() as usize;
+        let vertices = core_vertices(self, threshold);
+        let graph = self.induced_subgraph(&vertices);
+        let n_local = vertices.len();
+        let two_hop = (gamma >= 0.5).then(|| {
+            (0..n_local)
+                .map(|v| {
+                    let mut reach = graph.matrix[v].clone();
+                    for u in graph.get_neighbours(v) {
+                        reach.union_with(&graph.matrix[u]);
+                    }
+                    reach
+                })
+                .collect()
+        });
+        let mut search = QuasiSearch {
+            graph: &graph,
+            gamma,
+            min_size,
+            two_hop,
+            set: Vec::new(),
+            members: Bitvec::new(n_local),
+            maximal: Vec::new(),
+            seen: 0,
+            nodes: 0,
+            budget: Budget::new(limits, interrupt),
+        };
+        let cand = Bitvec::from_vector(&(0..n_local).collect::<Vec<usize>>(), n_local);
+        if search.budget.check().is_continue() {
+            let _ = search.expand(cand);
+        }
+        let mut sets: Vec<Vec<usize>> = search
+            .maximal
+            .iter()
+            .filter(|set| limits.max_size.is_none_or(|max| set.n_elements() <= max))
+            .map(|set| set.elements().into_iter().map(|v| vertices[v]).collect())
+            .collect();
+        sets.sort();
+        if let Some(max) = limits.max_results {
+            if sets.len() as u64 > max {
+                sets.truncate(max as usize);
+                let _ = search.budget.stop(StopReason::MaxResults);
+            }
+        }
+        search.budget.results = sets.len() as u64;
+        (sets, search.budget.outcome())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{maximal_cliques, pseudo_random_graph};
+
+    fn test_graph() -> Bitmatrix {
+        pseudo_random_graph(14, 7, 5, 3)
+    }
+
+    /// Every subset of the vertices satisfying `dense`, keeping the inclusion-maximal ones.
+    fn brute_force(graph: &Bitmatrix, dense: impl Fn(&[usize]) -> bool) -> Vec<Vec<usize>> {
+        let n_nodes = graph.n_nodes();
+        let sets: Vec<Vec<usize>> = (1u32..1 << n_nodes)
+            .map(|mask| {
+                (0..n_nodes)
+                    .filter(|&v| mask >> v & 1 == 1)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|set| dense(set))
+            .collect();
+        let mut maximal: Vec<Vec<usize>> = sets
+            .iter()
+            .filter(|set| {
+                !sets
+                    .iter()
+                    .any(|other| other.len() > set.len() && set.iter().all(|v| other.contains(v)))
+            })
+            .cloned()
+            .collect();
+        maximal.sort();
+        maximal
+    }
+
+    /// Sorted maximal k-plexes with at least `min_size` vertices.
+    fn k_plexes(graph: &Bitmatrix, k: usize, min_size: usize) -> Vec<Vec<usize>> {
+        let limits = Limits {
+            min_size,
+            ..Limits::default()
+        };
+        let mut plexes = Vec::new();
+        graph.visit_k_plexes_limited(k, &limits, &mut || false, &mut |plex: &[usize]| {
+            plexes.push(plex.to_vec());
+            ControlFlow::Continue(())
+        });
+        plexes.sort();
+        plexes
+    }
+
+    fn degree_in(graph: &Bitmatrix, v: usize, set: &[usize]) -> usize {
+        set.iter().filter(|&&u| graph.matrix[v].contains(u)).count()
+    }
+
+    #[test]
+    fn test_k_plexes() {
+        let graph = test_graph();
+        let mut cliques = maximal_cliques(&graph);
+        cliques.sort();
+        assert_eq!(k_plexes(&graph, 1, 0), cliques);
+
+        let expected = brute_force(&graph, |set| {
+            set.iter()
+                .all(|&v| degree_in(&graph, v, set) + 2 >= set.len())
+        });
+        assert_eq!(k_plexes(&graph, 2, 0), expected);
+        let large: Vec<Vec<usize>> = expected.into_iter().filter(|p| p.len() >= 5).collect();
+        assert_eq!(k_plexes(&graph, 2, 5), large);
+    }
+
+    #[test]
+    fn test_quasi_cliques() {
+        let graph = test_graph();
+        for gamma in [0.4, 0.75] {
+            let min_size = 4;
+            let mut expected = brute_force(&graph, |set| {
+                set.len() < min_size
+                    || set.iter().all(|&v| {
+                        degree_in(&graph, v, set) as f64 >= gamma * (set.len() - 1) as f64
+                    })
+            });
+            expected.retain(|set| set.len() >= min_size);
+            let limits = Limits {
+                min_size,
+                ..Limits::default()
+            };
+            let (sets, _) = graph.quasi_cliques_limited(gamma, &limits, &mut || false);
+            assert_eq!(sets, expected);
+
+            let limits = Limits {
+                max_size: Some(5),
+                ..limits
+            };
+            let (sets, outcome) = graph.quasi_cliques_limited(gamma, &limits, &mut || false);
+            expected.retain(|set| set.len() <= 5);
+            assert_eq!(sets, expected);
+            assert_eq!(outcome.results, expected.len() as u64);
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use crate::bitmatrix::{AllCliques, Bitmatrix};
+use crate::bitvec::Bitvec;
+use crate::checkpoint::{StateReader, StateWriter};
+use crate::random::Xorshift;
+use std::io;
+use std::sync::Arc;
+
+/// How the search picks the pivot `u` whose neighbours are skipped at each level.
+/// Every strategy enumerates the same cliques; they only change the shape (and size)
+/// of the search tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PivotStrategy {
+    /// Candidate of highest global degree among the neighbours of the previous pivot.
+    #[default]
+    Degree,
+    /// Tomita's rule: the vertex of P ∪ X maximising |P ∩ N(u)|.
+    Tomita,
+    /// A uniformly random vertex of P ∪ X, drawn from a generator seeded with the value.
+    Random(u64),
+    /// No pivoting at all, i.e. classic Bron–Kerbosch branching on every candidate.
+    NoPivot,
+}
+
+/// Counters describing the shape of a search tree, accumulated while the search runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    /// Branch nodes visited, one per vertex added to the clique under construction.
+    pub nodes: u64,
+    /// Largest clique size reached, i.e. the deepest level of the recursion.
+    pub max_depth: usize,
+    /// Pivots picked; stays zero without pivoting.
+    pub pivot_choices: u64,
+    /// Largest candidate set (the P of Bron–Kerbosch) of any node.
+    pub peak_candidates: usize,
+}
+
+impl SearchStats {
+    /// Counts a node at `depth` with `candidates` candidates left to branch on.
+    pub fn record_node(&mut self, depth: usize, candidates: usize) {
+        self.nodes += 1;
+        self.max_depth = self.max_depth.max(depth);
+        self.peak_candidates = self.peak_candidates.max(candidates);
+    }
+
+    /// Adds the counters of a search that ran `offset` levels below this one, e.g. behind
+    /// a fixed prefix of vertices.
+    pub fn merge(&mut self, other: &SearchStats, offset: usize) {
+        self.nodes += other.nodes;
+        if other.nodes > 0 {
+            self.max_depth = self.max_depth.max(other.max_depth + offset);
+        }
+        self.pivot_choices += other.pivot_choices;
+        self.peak_candidates = self.peak_candidates.max(other.peak_candidates);
+    }
+}
+
+/// A child of a search node: `vertex` joins the clique and `subg`/`cand`/`outside`
+/// describe what is left to explore below it. A branch with an empty `subg` is a maximal
+/// clique on its own.
+pub struct Branch {
+    pub vertex: usize,
+    pub subg: Bitvec,
+    pub cand: Bitvec,
+    pub outside: Bitvec,
+}
+
+/// Outcome of a bounded amount of search work, see `CliqueSearch::step`.
+pub enum Step<'a> {
+    Clique(&'a [usize]),
+    /// The node budget ran out before the next clique; call `step` again to continue.
+    Paused,
+    Exhausted,
+}
+
+/// Resumable state of the maximal clique enumeration.
+/// The search is iterative, so everything it needs between two cliques lives here:
+/// the clique under construction, the current candidate sets and the stack of
+/// suspended levels. The graph is passed in on every call so the state can be
+/// stored next to (rather than borrowing) the graph it walks.
+#[derive(Clone)]
+pub struct CliqueSearch {
+    // the clique being built, one vertex per level of the stack
+    clique: Vec<usize>,
+    subg: Bitvec,
+    cand: Bitvec,
+    ext_u: Bitvec,
+    u: Option<usize>,
+    stack: Vec<(Bitvec, Bitvec, Bitvec)>,
+    // adjacency into the graph of excluded vertices that are not part of it, and which of
+    // them are still adjacent to the whole clique, level by level like `stack`
+    excluded: Arc<[Bitvec]>,
+    outside: Bitvec,
+    outside_stack: Vec<Bitvec>,
+    pivot: PivotStrategy,
+    rng: Xorshift,
+    min_size: usize,
+    max_size: Option<usize>,
+    // search nodes visited since `step` last paused
+    since_pause: usize,
+    stats: SearchStats,
+    done: bool,
+}
+
+impl CliqueSearch {
+    pub fn new(graph: &Bitmatrix, pivot: PivotStrategy) -> Self {
+        let n_nodes = graph.n_nodes();
+        let mut cand = Bitvec::new(n_nodes);
+        for i in 0..n_nodes {
+            cand.insert(i)
+        }
+        let subg = cand.clone();
+        CliqueSearch::from_sets(graph, subg, cand, pivot)
+    }
+
+    /// Starts the search from an arbitrary subproblem: `cand` holds the vertices that may
+    /// still be added and `subg` holds those plus the excluded ones that must not be
+    /// extendable for a clique to count as maximal (the P ∪ X of Bron–Kerbosch).
+    pub fn from_sets(graph: &Bitmatrix, subg: Bitvec, cand: Bitvec, pivot: PivotStrategy) -> Self {
+        let excluded: Arc<[Bitvec]> = Arc::new([]);
+        CliqueSearch::from_sets_excluding(graph, subg, cand, excluded, Bitvec::new(0), pivot)
+    }
+
+    /// Like `from_sets`, with part of the excluded set kept outside the graph: row `x` of
+    /// `excluded` holds the neighbours in the graph of an excluded vertex, and `outside`
+    /// those of the rows that still count. This keeps the graph as small as the candidates
+    /// when most of the excluded vertices are never candidates themselves.
+    pub fn from_sets_excluding(
+        graph: &Bitmatrix,
+        subg: Bitvec,
+        cand: Bitvec,
+        excluded: Arc<[Bitvec]>,
+        outside: Bitvec,
+        pivot: PivotStrategy,
+    ) -> Self {
+        let seed = match pivot {
+            PivotStrategy::Random(seed) => seed,
+            _ => 0,
+        };
+        let mut search = CliqueSearch {
+            clique: Vec::new(),
+            done: cand.is_empty(),
+            ext_u: Bitvec::new(graph.n_nodes()),
+            subg,
+            cand,
+            u: None,
+            stack: Vec::new(),
+            excluded,
+            outside,
+            outside_stack: Vec::new(),
+            pivot,
+            rng: Xorshift::new(seed),
+            min_size: 0,
+            max_size: None,
+            since_pause: 0,
+            stats: SearchStats::default(),
+        };
+        search.stats.peak_candidates = search.cand.n_elements();
+        if !search.done {
+            search.ext_u = search.expansions(graph);
+        }
+        search
+    }
+
+    /// Only reports maximal cliques with between `min_size` and `max_size` vertices, and
+    /// skips the branches that cannot produce one. Sizes count the vertices added by this
+    /// search, so callers that prepend a fixed prefix should subtract its length.
+    pub fn with_size_bounds(mut self, min_size: usize, max_size: Option<usize>) -> Self {
+        self.min_size = min_size;
+        self.max_size = max_size;
+        self
+    }
+
+    /// Candidates worth branching on at the current level: those outside the pivot's
+    /// neighbourhood.
+    fn expansions(&mut self, graph: &Bitmatrix) -> Bitvec {
+        match self.pivot {
+            PivotStrategy::Degree => {
+                let pool = match self.u {
+                    Some(u) => self.cand.intersection(&graph.matrix[u]),
+                    None => self.cand.clone(),
+                };
+                if let Some(index) = graph.get_max_degree(&pool) {
+                    self.u = Some(index);
+                    self.stats.pivot_choices += 1;
+                }
+            }
+            PivotStrategy::Tomita => {
+                let best = self
+                    .subg
+                    .elements()
+                    .into_iter()
+                    .map(|w| (self.cand.intersection_count(&graph.matrix[w]), w))
+                    .max_by_key(|&(count, _)| count);
+                let best_outside = self
+                    .outside
+                    .elements()
+                    .into_iter()
+                    .map(|x| (self.cand.intersection_count(&self.excluded[x]), x))
+                    .max_by_key(|&(count, _)| count);
+                self.stats.pivot_choices += (best.is_some() || best_outside.is_some()) as u64;
+                self.u = best.map(|(_, w)| w);
+                if let Some((count, x)) = best_outside {
+                    if best.is_none_or(|(best, _)| count > best) {
+                        self.u = None;
+                        return self.cand.difference(&self.excluded[x]);
+                    }
+                }
+            }
+            PivotStrategy::Random(_) => {
+                let pool = self.subg.elements();
+                let outside = self.outside.elements();
+                let drawn = self.rng.below(pool.len() + outside.len());
+                self.stats.pivot_choices += 1;
+                if drawn >= pool.len() {
+                    self.u = None;
+                    return self
+                        .cand
+                        .difference(&self.excluded[outside[drawn - pool.len()]]);
+                }
+                self.u = Some(pool[drawn]);
+            }
+            PivotStrategy::NoPivot => return self.cand.clone(),
+        }
+        match self.u {
+            Some(u) => self.cand.difference(&graph.matrix[u]),
+            None => self.cand.clone(),
+        }
+    }
+
+    /// Excluded vertices outside the graph that are adjacent to `q` as well as to the
+    /// whole clique so far.
+    fn outside_of(&self, q: usize) -> Bitvec {
+        let mut outside = Bitvec::new(self.excluded.len());
+        for x in self.outside.elements() {
+            if self.excluded[x].contains(q) {
+                outside.insert(x);
+            }
+        }
+        outside
+    }
+
+    /// Writes everything needed to carry on from the current point, see `read_state`.
+    /// Only searches over a whole graph, without excluded vertices outside it, are saved.
+    pub(crate) fn write_state(&self, out: &mut StateWriter) {
+        debug_assert!(self.excluded.is_empty());
+        let (tag, seed) = match self.pivot {
+            PivotStrategy::Degree => (0, 0),
+            PivotStrategy::Tomita => (1, 0),
+            PivotStrategy::Random(seed) => (2, seed),
+            PivotStrategy::NoPivot => (3, 0),
+        };
+        out.u64(tag);
+        out.u64(seed);
+        out.u64(self.rng.state());
+        out.usizes(&self.clique);
+        out.bitvec(&self.subg);
+        out.bitvec(&self.cand);
+        out.bitvec(&self.ext_u);
+        out.usize(self.u.map_or(0, |u| u + 1));
+        out.usize(self.stack.len());
+        for (subg, cand, ext_u) in &self.stack {
+            out.bitvec(subg);
+            out.bitvec(cand);
+            out.bitvec(ext_u);
+        }
+        out.usize(self.min_size);
+        out.usize(self.max_size.map_or(0, |max| max + 1));
+        out.usize(self.done as usize);
+        out.u64(self.stats.nodes);
+        out.usize(self.stats.max_depth);
+        out.u64(self.stats.pivot_choices);
+        out.usize(self.stats.peak_candidates);
+    }
+
+    /// Rebuilds a search saved by `write_state` on a graph with `n_nodes` vertices.
+    pub(crate) fn read_state(input: &mut StateReader, n_nodes: usize) -> io::Result<Self> {
+        let pivot = match (input.u64()?, input.u64()?) {
+            (0, _) => PivotStrategy::Degree,
+            (1, _) => PivotStrategy::Tomita,
+            (2, seed) => PivotStrategy::Random(seed),
+            (3, _) => PivotStrategy::NoPivot,
+            (tag, _) => return Err(StateReader::invalid(format!("unknown pivot {}", tag))),
+        };
+        let rng = Xorshift::from_state(input.u64()?);
+        let clique = input.usizes()?;
+        let subg = input.bitvec(n_nodes)?;
+        let cand = input.bitvec(n_nodes)?;
+        let ext_u = input.bitvec(n_nodes)?;
+        let u = input.usize()?.checked_sub(1);
+        let depth = input.usize()?;
+        let mut stack = Vec::new();
+        for _ in 0..depth {
+            stack.push((
+                input.bitvec(n_nodes)?,
+                input.bitvec(n_nodes)?,
+                input.bitvec(n_nodes)?,
+            ));
+        }
+        if clique.iter().chain(&u).any(|&v| v >= n_nodes) {
+            return Err(StateReader::invalid("vertex out of range".to_string()));
+        }
+        Ok(CliqueSearch {
+            clique,
+            subg,
+            cand,
+            ext_u,
+            u,
+            stack,
+            excluded: Arc::new([]),
+            outside: Bitvec::new(0),
+            outside_stack: Vec::new(),
+            pivot,
+            rng,
+            min_size: input.usize()?,
+            max_size: input.usize()?.checked_sub(1),
+            since_pause: 0,
+            done: input.usize()? != 0,
+            stats: SearchStats {
+                nodes: input.u64()?,
+                max_depth: input.usize()?,
+                pivot_choices: input.u64()?,
+                peak_candidates: input.usize()?,
+            },
+        })
+    }
+
+    /// Shape of the search tree explored so far.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    /// Whether every maximal clique has been reported.
+    pub fn is_exhausted(&self) -> bool {
+        self.done
+    }
+
+    /// Expands the top level of a fresh search into its independent branches, so they can
+    /// be explored separately (e.g. on different threads). Branches that cannot lead to a
+    /// maximal clique are dropped.
+    pub fn into_branches(mut self, graph: &Bitmatrix) -> Vec<Branch> {
+        let mut branches = Vec::new();
+        while let Some(q) = self.ext_u.pop() {
+            self.cand.remove(q);
+            let adj_q = &graph.matrix[q];
+            let subg = self.subg.intersection(adj_q);
+            let cand = self.cand.intersection(adj_q);
+            let outside = self.outside_of(q);
+            if (subg.is_empty() && outside.is_empty()) || !cand.is_empty() {
+                branches.push(Branch {
+                    vertex: q,
+                    subg,
+                    cand,
+                    outside,
+                });
+            }
+        }
+        branches
+    }
+
+    /// Advances the search to the next maximal clique. The returned slice is in discovery
+    /// order and is only valid until the next call.
+    pub fn next_clique(&mut self, graph: &Bitmatrix) -> Option<&[usize]> {
+        match self.step(graph, usize::MAX) {
+            Step::Clique(clique) => Some(clique),
+            Step::Paused | Step::Exhausted => None,
+        }
+    }
+
+    /// Like `next_clique`, but pauses every `max_nodes` search nodes (counted across
+    /// calls) so the caller can look at the clock (or for signals) in between.
+    pub fn step(&mut self, graph: &Bitmatrix, max_nodes: usize) -> Step<'_> {
+        if self.done {
+            return Step::Exhausted;
+        }
+        loop {
+            if self.since_pause >= max_nodes {
+                self.since_pause = 0;
+                return Step::Paused;
+            }
+            self.since_pause += 1;
+            match self.ext_u.pop() {
+                Some(q) => {
+                    self.cand.remove(q);
+                    self.clique.truncate(self.stack.len());
+                    self.clique.push(q);
+                    let size = self.clique.len();
+                    self.stats.nodes += 1;
+                    self.stats.max_depth = self.stats.max_depth.max(size);
+                    let adj_q = &graph.matrix[q];
+                    let subg_q = self.subg.intersection(adj_q);
+                    let outside_q = self.outside_of(q);
+                    if subg_q.is_empty() {
+                        if outside_q.is_empty()
+                            && size >= self.min_size
+                            && self.max_size.is_none_or(|max| size <= max)
+                        {
+                            return Step::Clique(&self.clique);
+                        }
+                        continue;
+                    }
+                    // every maximal clique below would be larger than allowed
+                    if self.max_size.is_some_and(|max| size >= max) {
+                        continue;
+                    }
+                    let cand_q = self.cand.intersection(adj_q);
+                    let n_cand = cand_q.n_elements();
+                    self.stats.peak_candidates = self.stats.peak_candidates.max(n_cand);
+                    if n_cand > 0 && size + n_cand >= self.min_size {
+                        let subg = std::mem::replace(&mut self.subg, subg_q);
+                        let cand = std::mem::replace(&mut self.cand, cand_q);
+                        let outside = std::mem::replace(&mut self.outside, outside_q);
+                        self.outside_stack.push(outside);
+                        let ext_u = self.expansions(graph);
+                        self.stack
+                            .push((subg, cand, std::mem::replace(&mut self.ext_u, ext_u)));
+                    }
+                }
+                None => match self.stack.pop() {
+                    Some((subg_prev, cand_prev, ext_u_prev)) => {
+                        self.subg = subg_prev;
+                        self.cand = cand_prev;
+                        self.ext_u = ext_u_prev;
+                        if let Some(outside) = self.outside_stack.pop() {
+                            self.outside = outside;
+                        }
+                    }
+                    None => {
+                        self.done = true;
+                        return Step::Exhausted;
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Iterator over the maximal cliques of a graph, each one sorted. It owns the graph, so
+/// it can be handed to Python as is.
+pub struct Cliques {
+    graph: Bitmatrix,
+    search: CliqueSearch,
+}
+
+impl Cliques {
+    pub fn new(graph: Bitmatrix, pivot: PivotStrategy) -> Self {
+        let search = CliqueSearch::new(&graph, pivot);
+        Cliques { graph, search }
+    }
+}
+
+impl Iterator for Cliques {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut clique = self.search.next_clique(&self.graph)?.to_vec();
+        clique.sort_unstable();
+        Some(clique)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::graph_where;
+
+    #[test]
+    fn test_pivot_strategies_agree() {
+        let n_nodes = 30;
+        let graph = graph_where(n_nodes, |u, v| (u * v + u + v) % 3 != 0);
+        let mut expected: Vec<Vec<usize>> =
+            Cliques::new(graph.clone(), PivotStrategy::NoPivot).collect();
+        expected.sort();
+        for pivot in [
+            PivotStrategy::Degree,
+            PivotStrategy::Tomita,
+            PivotStrategy::Random(7),
+        ] {
+            let mut found: Vec<Vec<usize>> = Cliques::new(graph.clone(), pivot).collect();
+            found.sort();
+            assert_eq!(found, expected);
+        }
+
+        let largest = expected.iter().map(|clique| clique.len()).max().unwrap();
+        for pivot in [PivotStrategy::NoPivot, PivotStrategy::Tomita] {
+            let mut search = CliqueSearch::new(&graph, pivot);
+            while search.next_clique(&graph).is_some() {}
+            let stats = search.stats();
+            assert_eq!(stats.max_depth, largest);
+            assert!(stats.nodes >= expected.len() as u64);
+            assert_eq!(stats.peak_candidates, n_nodes);
+            assert_eq!(stats.pivot_choices == 0, pivot == PivotStrategy::NoPivot);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Graphs shared by the unit tests.
+
+use crate::bitmatrix::Bitmatrix;
+use crate::search::{Cliques, PivotStrategy};
+
+/// Undirected graph on `n_nodes` vertices with the given edges.
+pub fn graph_from_edges(edges: &[(usize, usize)], n_nodes: usize) -> Bitmatrix {
+    let mut rows = vec![Vec::new(); n_nodes];
+    for &(u, v) in edges {
+        rows[u].push(v);
+        rows[v].push(u);
+    }
+    Bitmatrix::new(rows, n_nodes)
+}
+
+/// Graph on `n_nodes` vertices where distinct `u` and `v` are adjacent when `adjacent(u, v)`,
+/// which must be symmetric.
+pub fn graph_where(n_nodes: usize, adjacent: impl Fn(usize, usize) -> bool) -> Bitmatrix {
+    let rows: Vec<Vec<usize>> = (0..n_nodes)
+        .map(|u| (0..n_nodes).filter(|&v| v != u && adjacent(u, v)).collect())
+        .collect();
+    Bitmatrix::new(rows, n_nodes)
+}
+
+/// Irregular but reproducible test graph: `u` and `v` are adjacent when
+/// `(a·u + a·v + u·v) mod m < c`, so `c / m` roughly sets the density.
+pub fn pseudo_random_graph(n_nodes: usize, a: usize, m: usize, c: usize) -> Bitmatrix {
+    graph_where(n_nodes, |u, v| (a * u + a * v + u * v) % m < c)
+}
+
+/// Every maximal clique of `graph`, each sorted, as `iter_cliques` yields them.
+pub fn maximal_cliques(graph: &Bitmatrix) -> Vec<Vec<usize>> {
+    Cliques::new(graph.clone(), PivotStrategy::default()).collect()
+}
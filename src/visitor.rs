@@ -0,0 +1,14 @@
+use std::ops::ControlFlow;
+
+/// Receives cliques one at a time while they are being enumerated.
+/// Returning `ControlFlow::Break` stops the enumeration right after the current clique,
+/// which makes it possible to aggregate (or search) without materialising every clique.
+pub trait CliqueVisitor {
+    fn visit(&mut self, clique: &[usize]) -> ControlFlow<()>;
+}
+
+impl<F: FnMut(&[usize]) -> ControlFlow<()>> CliqueVisitor for F {
+    fn visit(&mut self, clique: &[usize]) -> ControlFlow<()> {
+        self(clique)
+    }
+}